@@ -0,0 +1,196 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{
+    graph::{NodeId, TypeGraph},
+    held_locals::suspend_states,
+    options::AwaitStatsOrder,
+    schema::*,
+};
+
+/// Statistics of awaits of a coroutine.
+#[derive(Debug)]
+pub struct AwaitStats<'a> {
+    pub type_: &'a Type,
+    /// The maximum nesting of awaitees, 0 if nothing is awaited.
+    pub depth: usize,
+    /// The number of suspend states, i.e. await points.
+    pub suspends: usize,
+    /// The number of distinct types of awaitees, every awaitee without a type
+    /// name is counted separately.
+    pub awaitees: usize,
+    pub largest_awaitee: Option<&'a Field>,
+}
+
+impl AwaitStats<'_> {
+    /// The share of the largest awaitee in the coroutine's size, in percent.
+    pub fn share(&self) -> f64 {
+        let size = self.largest_awaitee.map_or(0, |f| f.size);
+        size as f64 * 100. / self.type_.size.max(1) as f64
+    }
+}
+
+/// Collects statistics of all coroutines.
+///
+/// Awaitees are followed by their type names, so only types of `types` are
+/// counted in the depth, other awaitees are counted as leaves.
+pub fn collect(types: &[Type], order: AwaitStatsOrder) -> Vec<AwaitStats<'_>> {
+    let graph = TypeGraph::new(types, false);
+    let mut depths = HashMap::new();
+
+    let mut stats = (0..types.len())
+        .filter(|&id| is_coroutine(&types[id]))
+        .map(|id| {
+            let type_ = &types[id];
+            let TypeKind::Enum(enum_) = &type_.kind else {
+                unreachable!("coroutines are enums");
+            };
+
+            let largest_awaitee =
+                awaitees(type_).reduce(|max, f| if f.size > max.size { f } else { max });
+
+            // Awaitees without type names can't be told apart.
+            let untyped = awaitees(type_).filter(|f| f.local_type.is_none()).count();
+            let mut typed = awaitees(type_)
+                .filter_map(|f| f.local_type.as_deref())
+                .collect::<Vec<_>>();
+            typed.sort_unstable();
+            typed.dedup();
+
+            AwaitStats {
+                type_,
+                depth: depth(&graph, id, &mut depths, &mut Vec::new()),
+                suspends: enum_.variants.iter().flat_map(suspend_states).count(),
+                awaitees: typed.len() + untyped,
+                largest_awaitee,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    match order {
+        // Types are already sorted by size.
+        AwaitStatsOrder::Size => {}
+        AwaitStatsOrder::Depth => stats.sort_by_key(|s| Reverse(s.depth)),
+        AwaitStatsOrder::Suspends => stats.sort_by_key(|s| Reverse(s.suspends)),
+        AwaitStatsOrder::Awaitees => stats.sort_by_key(|s| Reverse(s.awaitees)),
+        AwaitStatsOrder::Share => stats.sort_by(|a, b| b.share().total_cmp(&a.share())),
+    }
+
+    stats
+}
+
+fn is_coroutine(type_: &Type) -> bool {
+    match &type_.kind {
+        TypeKind::Enum(e) => e
+            .variants
+            .iter()
+            .any(|v| v.name.split(", ").any(|name| name == "Unresumed")),
+        TypeKind::Struct(_) => false,
+    }
+}
+
+fn awaitees(type_: &Type) -> impl Iterator<Item = &Field> {
+    type_
+        .fields()
+        .filter(|f| f.kind == FieldKind::GeneratorLocal && f.name == "__awaitee")
+}
+
+fn depth(
+    graph: &TypeGraph<'_>,
+    id: NodeId,
+    depths: &mut HashMap<NodeId, usize>,
+    path: &mut Vec<NodeId>,
+) -> usize {
+    if let Some(&depth) = depths.get(&id) {
+        return depth;
+    }
+
+    path.push(id);
+
+    let mut max = 0;
+    for awaitee in awaitees(graph.type_(id)) {
+        let child = awaitee
+            .local_type
+            .as_deref()
+            .and_then(|name| graph.find(name))
+            .filter(|child| !path.contains(child));
+
+        let nested = child.map_or(0, |child| depth(graph, child, depths, path));
+        max = max.max(nested + 1);
+    }
+
+    path.pop();
+    depths.insert(id, max);
+    max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_collect() {
+        let input = [
+            "type: `{async fn body of main()}`: 40 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `Unresumed`: 0 bytes",
+            "    variant `Suspend0`: 24 bytes",
+            "        local `.__awaitee`: 24 bytes, alignment: 8 bytes, type: {async fn body of serve()}",
+            "    variant `Suspend1, Suspend2`: 16 bytes",
+            "        local `.__awaitee`: 16 bytes, alignment: 8 bytes, type: {async fn body of wait()}",
+            "type: `{async fn body of serve()}`: 24 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `Unresumed`: 0 bytes",
+            "    variant `Suspend0`: 24 bytes",
+            "        local `.__awaitee`: 24 bytes, alignment: 8 bytes, type: tokio::time::Sleep",
+            "type: `{async fn body of wait()}`: 1 bytes, alignment: 1 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `Unresumed`: 0 bytes",
+            "    variant `Returned`: 0 bytes",
+            "    variant `Panicked`: 0 bytes",
+            "type: `tokio::time::Sleep`: 24 bytes, alignment: 8 bytes",
+            "    field `.deadline`: 24 bytes",
+        ]
+        .join("\n");
+        let types = parser::parse(&input).unwrap();
+
+        let summary = |order| {
+            collect(&types, order)
+                .iter()
+                .map(|s| (s.type_.name.clone(), s.depth, s.suspends, s.awaitees))
+                .collect::<Vec<_>>()
+        };
+
+        let main = ("{async fn body of main()}".to_string(), 2, 3, 2);
+        let serve = ("{async fn body of serve()}".to_string(), 1, 1, 1);
+        let wait = ("{async fn body of wait()}".to_string(), 0, 0, 0);
+
+        assert_eq!(
+            summary(AwaitStatsOrder::Size),
+            [main.clone(), serve.clone(), wait.clone()]
+        );
+        assert_eq!(summary(AwaitStatsOrder::Share), [serve, main, wait]);
+    }
+
+    #[test]
+    fn test_collect_untyped() {
+        let input = [
+            "type: `{async fn body of main()}`: 40 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `Unresumed`: 0 bytes",
+            "    variant `Suspend0`: 24 bytes",
+            "        local `.__awaitee`: 24 bytes, alignment: 8 bytes",
+            "    variant `Suspend1`: 16 bytes",
+            "        local `.__awaitee`: 16 bytes, alignment: 8 bytes",
+        ]
+        .join("\n");
+        let types = parser::parse(&input).unwrap();
+
+        let stats = collect(&types, AwaitStatsOrder::Size);
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].depth, 1);
+        assert_eq!(stats[0].suspends, 2);
+        assert_eq!(stats[0].awaitees, 2);
+        assert_eq!(stats[0].largest_awaitee.map(|f| f.size), Some(24));
+    }
+}
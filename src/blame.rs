@@ -0,0 +1,148 @@
+use std::cmp::Reverse;
+
+use crate::{
+    graph::{EdgeKind, NodeId, TypeGraph},
+    schema::*,
+};
+
+/// A breakdown of a type's size by its largest variant.
+///
+/// Sizes of parts are inclusive: nested types are broken down further, so
+/// every level sums up to the size of the containing part.
+#[derive(Debug)]
+pub struct Blame<'a> {
+    pub type_: &'a Type,
+    /// The largest variant if the type is an enum.
+    pub variant: Option<&'a EnumVariant>,
+    /// Parts sorted in descending order by size.
+    pub parts: Vec<Part<'a>>,
+}
+
+#[derive(Debug)]
+pub struct Part<'a> {
+    pub size: usize,
+    pub kind: PartKind<'a>,
+}
+
+#[derive(Debug)]
+pub enum PartKind<'a> {
+    /// A field, `nested` is set if its type is known (e.g. for awaitees).
+    Field {
+        field: &'a Field,
+        nested: Option<Blame<'a>>,
+    },
+    Padding,
+    Discriminant,
+    EndPadding,
+    /// Paddings and fields removed by other options (e.g. `-h`).
+    Other,
+}
+
+/// Breaks down the type's size, following fields by their type names.
+pub fn blame<'a>(graph: &TypeGraph<'a>, id: NodeId) -> Blame<'a> {
+    blame_type(graph, id, &mut Vec::new())
+}
+
+fn blame_type<'a>(graph: &TypeGraph<'a>, id: NodeId, path: &mut Vec<NodeId>) -> Blame<'a> {
+    let type_ = graph.type_(id);
+    path.push(id);
+
+    let mut parts = Vec::new();
+    let mut add = |size, kind| parts.push(Part { size, kind });
+
+    let (variant, items) = match &type_.kind {
+        TypeKind::Struct(s) => (None, &s.items[..]),
+        TypeKind::Enum(e) => {
+            if let Some(size) = e.discriminant_size {
+                add(size, PartKind::Discriminant);
+            }
+
+            let variant = e.largest_variant();
+            (variant, variant.map_or(&[][..], |v| &v.items[..]))
+        }
+    };
+
+    for item in items {
+        match item {
+            FieldOrPadding::Field(field) => {
+                let nested = graph
+                    .children(id)
+                    .find(|edge| edge.kind == EdgeKind::Exact && std::ptr::eq(edge.field, field))
+                    .filter(|edge| !path.contains(&edge.child))
+                    .map(|edge| blame_type(graph, edge.child, path));
+
+                add(field.size, PartKind::Field { field, nested });
+            }
+            FieldOrPadding::Padding(size) => add(*size, PartKind::Padding),
+        }
+    }
+
+    if let Some(size) = type_.end_padding {
+        add(size, PartKind::EndPadding);
+    }
+
+    let total = parts.iter().map(|p| p.size).sum::<usize>();
+    if total < type_.size {
+        parts.push(Part {
+            size: type_.size - total,
+            kind: PartKind::Other,
+        });
+    }
+
+    // Use stable sort to keep the layout order of equal parts.
+    parts.sort_by_key(|p| Reverse(p.size));
+
+    path.pop();
+    Blame {
+        type_,
+        variant,
+        parts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_blame() {
+        let input = [
+            "type: `{async fn body of main()}`: 32 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `Suspend0`: 24 bytes",
+            "        padding: 7 bytes",
+            "        local `.__awaitee`: 16 bytes, alignment: 8 bytes, type: {async fn body of serve()}",
+            "    variant `Suspend1`: 15 bytes",
+            "        padding: 7 bytes",
+            "        local `.buf`: 8 bytes, alignment: 8 bytes",
+            "type: `{async fn body of serve()}`: 16 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `Suspend0`: 12 bytes",
+            "        padding: 3 bytes",
+            "        local `.conn`: 4 bytes, alignment: 4 bytes",
+            "        local `.timer`: 5 bytes, alignment: 1 bytes",
+        ]
+        .join("\n");
+
+        let types = parser::parse(&input).unwrap();
+        let graph = TypeGraph::new(&types, false);
+        let sizes = |blame: &Blame<'_>| blame.parts.iter().map(|p| p.size).collect::<Vec<_>>();
+
+        let main = blame(&graph, 0);
+        assert_eq!(main.variant.unwrap().name, "Suspend0");
+        assert_eq!(sizes(&main), [16, 8, 7, 1]);
+        assert!(matches!(main.parts[1].kind, PartKind::Other));
+
+        let PartKind::Field { field, nested } = &main.parts[0].kind else {
+            panic!("expected a field");
+        };
+        assert_eq!(field.name, "__awaitee");
+
+        // The awaitee is broken down by its own largest variant.
+        let serve = nested.as_ref().unwrap();
+        assert_eq!(serve.type_.name, "{async fn body of serve()}");
+        assert_eq!(sizes(serve), [5, 4, 3, 3, 1]);
+        assert!(matches!(serve.parts[3].kind, PartKind::Other));
+    }
+}
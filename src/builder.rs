@@ -0,0 +1,215 @@
+use std::{
+    env, fs,
+    io::{BufRead, BufReader},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use eyre::WrapErr;
+use serde::Deserialize;
+
+use crate::reader;
+
+/// Builds a project with `-Zprint-type-sizes` in a fresh target directory and
+/// returns the compiler's output.
+///
+/// Only type sizes are captured, each compilation unit is preceded by a crate
+/// header (see [`reader::crate_header`]).
+///
+/// The output is also saved to `save_to` to reuse it without rebuilding.
+pub fn build(toolchain: &str, cargo_args: &[String], save_to: &Path) -> eyre::Result<String> {
+    if let Some(arg) = cargo_args
+        .iter()
+        .find(|arg| arg.starts_with("-j") || arg.starts_with("--jobs"))
+    {
+        eyre::bail!("`{arg}` isn't allowed, the build is always run with -j1");
+    }
+
+    if let Some(arg) = cargo_args
+        .iter()
+        .find(|arg| arg.starts_with("--message-format"))
+    {
+        eyre::bail!("`{arg}` isn't allowed, cargo's messages are used to detect crates");
+    }
+
+    // A fresh target directory, otherwise cached crates aren't printed.
+    let target_dir = tempfile::Builder::new()
+        .prefix("top-type-sizes-")
+        .tempdir()
+        .wrap_err("cannot create a target directory")?;
+
+    let mut command = command(toolchain, cargo_args, target_dir.path());
+    eprintln!("running {command:?}");
+
+    // Cargo's messages and diagnostics go to stderr as is, while stdout
+    // contains type sizes and JSON messages delimiting compilation units.
+    command.stdout(Stdio::piped());
+    let mut child = command.spawn().wrap_err("cannot run cargo")?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    let mut output = String::new();
+    let mut unit = String::new();
+    for line in BufReader::new(stdout).split(b'\n') {
+        let line = String::from_utf8_lossy(&line?).into_owned();
+
+        if line.starts_with("print-type-size ") {
+            unit.push_str(&line);
+            unit.push('\n');
+        } else if let Some((name, version)) = compiled_package(&line) {
+            // Types of a unit are printed before its artifact is reported.
+            if !unit.is_empty() {
+                output.push_str(&reader::crate_header(&name, &version));
+                output.push_str(&unit);
+                unit.clear();
+            }
+        } else if !line.starts_with('{') {
+            eprintln!("{line}");
+        }
+    }
+
+    let status = child.wait().wrap_err("cannot wait for cargo")?;
+    if !status.success() {
+        eyre::bail!("cargo failed: {status}");
+    }
+
+    fs::write(save_to, &output)
+        .wrap_err_with(|| format!("cannot save output to {}", save_to.display()))?;
+
+    Ok(output)
+}
+
+fn command(toolchain: &str, cargo_args: &[String], target_dir: &Path) -> Command {
+    let mut rustflags = env::var_os("RUSTFLAGS").unwrap_or_default();
+    if !rustflags.is_empty() {
+        rustflags.push(" ");
+    }
+    rustflags.push("-Zprint-type-sizes");
+
+    // Not `$CARGO`, only the rustup proxy accepts `+toolchain`.
+    let mut command = Command::new("cargo");
+
+    command
+        .arg(format!("+{toolchain}"))
+        .arg("build")
+        // Parallel builds shuffle the output.
+        .arg("-j1")
+        .arg("--message-format=json-render-diagnostics")
+        .args(cargo_args)
+        .env("RUSTFLAGS", rustflags)
+        .env("CARGO_TARGET_DIR", target_dir);
+
+    command
+}
+
+#[derive(Deserialize)]
+struct Message {
+    reason: String,
+    package_id: Option<String>,
+}
+
+/// Returns the name and the version of a package from cargo's
+/// `compiler-artifact` message.
+fn compiled_package(line: &str) -> Option<(String, String)> {
+    let message = serde_json::from_str::<Message>(line).ok()?;
+    if message.reason != "compiler-artifact" {
+        return None;
+    }
+
+    parse_package_id(&message.package_id?)
+}
+
+// Examples:
+// * "tokio 1.36.0 (registry+https://github.com/rust-lang/crates.io-index)"
+// * "registry+https://github.com/rust-lang/crates.io-index#tokio@1.36.0"
+// * "path+file:///home/user/chat#0.1.0"
+fn parse_package_id(id: &str) -> Option<(String, String)> {
+    let Some((source, spec)) = id.rsplit_once('#') else {
+        let mut parts = id.split(' ');
+        let name = parts.next()?;
+        let version = parts.next()?;
+        return Some((name.into(), version.into()));
+    };
+
+    // The name is omitted if it matches the last segment of the source.
+    let (name, version) = match spec.split_once('@') {
+        Some((name, version)) => (name, version),
+        None => {
+            let path = source.split('?').next()?;
+            (path.trim_end_matches('/').rsplit('/').next()?, spec)
+        }
+    };
+
+    Some((name.into(), version.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        let args = ["--example".into(), "chat".into()];
+        let command = command("nightly", &args, Path::new("/tmp/target"));
+
+        let args = command.get_args().collect::<Vec<_>>();
+        assert_eq!(
+            args,
+            [
+                "+nightly",
+                "build",
+                "-j1",
+                "--message-format=json-render-diagnostics",
+                "--example",
+                "chat"
+            ]
+        );
+
+        let target_dir = command
+            .get_envs()
+            .find(|(key, _)| *key == "CARGO_TARGET_DIR")
+            .and_then(|(_, value)| value);
+        assert_eq!(target_dir, Some("/tmp/target".as_ref()));
+
+        let rustflags = command
+            .get_envs()
+            .find(|(key, _)| *key == "RUSTFLAGS")
+            .and_then(|(_, value)| value)
+            .unwrap();
+        assert!(rustflags.to_str().unwrap().ends_with("-Zprint-type-sizes"));
+    }
+
+    #[test]
+    fn test_compiled_package() {
+        let line =
+            r#"{"reason":"compiler-artifact","package_id":"path+file:///home/user/chat#0.1.0"}"#;
+        assert_eq!(
+            compiled_package(line),
+            Some(("chat".into(), "0.1.0".into()))
+        );
+
+        let line = r#"{"reason":"build-script-executed","package_id":"chat 0.1.0 (path+file:///home/user/chat)"}"#;
+        assert_eq!(compiled_package(line), None);
+        assert_eq!(compiled_package("print-type-size type: `A`"), None);
+    }
+
+    #[test]
+    fn test_parse_package_id() {
+        let tokio = Some(("tokio".into(), "1.36.0".into()));
+        let id = "tokio 1.36.0 (registry+https://github.com/rust-lang/crates.io-index)";
+        assert_eq!(parse_package_id(id), tokio);
+        let id = "registry+https://github.com/rust-lang/crates.io-index#tokio@1.36.0";
+        assert_eq!(parse_package_id(id), tokio);
+        let id = "git+https://github.com/tokio-rs/tokio?branch=master#tokio@1.36.0";
+        assert_eq!(parse_package_id(id), tokio);
+        let id = "git+https://github.com/tokio-rs/tokio/?rev=a1b2#1.36.0";
+        assert_eq!(parse_package_id(id), tokio);
+    }
+
+    #[test]
+    fn test_forbidden_args() {
+        let save_to = Path::new("/nonexistent/type-sizes.txt");
+        for arg in ["-j4", "--jobs=4", "--message-format=short"] {
+            assert!(build("nightly", &[arg.into()], save_to).is_err());
+        }
+    }
+}
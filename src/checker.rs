@@ -0,0 +1,168 @@
+use std::{collections::HashSet, fmt};
+
+use regex::Regex;
+
+use crate::schema::*;
+
+/// A limit for sizes of types matching the pattern.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub pattern: Regex,
+    pub max_size: usize,
+    /// A line number in the budget file.
+    pub line_no: usize,
+    /// The rule as written in the budget file.
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum Violation<'a> {
+    TooLarge {
+        rule: &'a Rule,
+        type_name: String,
+        size: usize,
+    },
+    Unmatched {
+        rule: &'a Rule,
+    },
+}
+
+impl Violation<'_> {
+    pub fn rule(&self) -> &Rule {
+        match self {
+            Self::TooLarge { rule, .. } | Self::Unmatched { rule } => rule,
+        }
+    }
+}
+
+impl fmt::Display for Violation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge {
+                rule,
+                type_name,
+                size,
+            } => write!(
+                f,
+                "{type_name}: {size} bytes > {} bytes (line {}: {})",
+                rule.max_size, rule.line_no, rule.text
+            ),
+            Self::Unmatched { rule } => write!(
+                f,
+                "no types match the rule (line {}: {})",
+                rule.line_no, rule.text
+            ),
+        }
+    }
+}
+
+/// Parses a budget file.
+///
+/// Every non-empty line, except comments starting with `#`, is a rule:
+/// ```text
+/// <pattern> <= <max size in bytes>
+/// ```
+///
+/// Patterns are regex (in the regex crate's syntax), the same as for the
+/// -f/--filter option. A pattern wrapped into backticks matches the exact
+/// type name, e.g. `` `{async fn body of handle_request()}` <= 2048 ``.
+pub fn parse_rules(input: &str) -> eyre::Result<Vec<Rule>> {
+    let mut rules = Vec::new();
+
+    for (line, line_no) in input.lines().zip(1..) {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((pattern, max_size)) = line.rsplit_once("<=") else {
+            eyre::bail!("line {line_no}: expected `<pattern> <= <size>`, got `{line}`");
+        };
+
+        let pattern = pattern.trim();
+        let pattern = match pattern.strip_prefix('`').and_then(|p| p.strip_suffix('`')) {
+            Some(name) => Regex::new(&format!("^{}$", regex::escape(name))),
+            None => Regex::new(pattern),
+        }
+        .map_err(|err| eyre::eyre!("line {line_no}: invalid pattern: {err}"))?;
+
+        let max_size = max_size
+            .trim()
+            .parse()
+            .map_err(|err| eyre::eyre!("line {line_no}: invalid size: {err}"))?;
+
+        rules.push(Rule {
+            pattern,
+            max_size,
+            line_no,
+            text: line.into(),
+        });
+    }
+
+    Ok(rules)
+}
+
+/// Checks types against rules. If `deny_unmatched` is set, rules matching no
+/// types are also reported.
+pub fn check<'a>(types: &[Type], rules: &'a [Rule], deny_unmatched: bool) -> Vec<Violation<'a>> {
+    let mut violations = Vec::new();
+
+    for rule in rules {
+        let mut matched = false;
+
+        for type_ in types.iter().filter(|t| rule.pattern.is_match(&t.name)) {
+            matched = true;
+
+            if type_.size > rule.max_size {
+                violations.push(Violation::TooLarge {
+                    rule,
+                    type_name: type_.name.clone(),
+                    size: type_.size,
+                });
+            }
+        }
+
+        if !matched && deny_unmatched {
+            violations.push(Violation::Unmatched { rule });
+        }
+    }
+
+    violations
+}
+
+/// Summarizes violations, a rule violated by several types is counted once.
+pub fn summary(violations: &[Violation<'_>], rules: &[Rule]) -> String {
+    let violated = violations
+        .iter()
+        .map(|v| v.rule().line_no)
+        .collect::<HashSet<_>>();
+
+    format!("{} of {} rules violated", violated.len(), rules.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = parse_rules(
+            "# futures\n\n`{async fn body of run()}` <= 2048\n  process\\(\\) <= 512  \n",
+        )
+        .unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].line_no, 3);
+        assert_eq!(rules[0].max_size, 2048);
+        assert!(rules[0].pattern.is_match("{async fn body of run()}"));
+        assert!(!rules[0].pattern.is_match("{async fn body of run()}>"));
+        assert_eq!(rules[1].line_no, 4);
+        assert_eq!(rules[1].max_size, 512);
+        assert!(rules[1].pattern.is_match("{async fn body of process()}"));
+
+        assert!(parse_rules("process").is_err());
+        assert!(parse_rules("process <= big").is_err());
+        assert!(parse_rules("{process <= 8").is_err());
+    }
+}
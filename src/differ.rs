@@ -0,0 +1,257 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+use serde::Serialize;
+
+use crate::{options::Options, schema::*, transformer};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum Status {
+    Added,
+    Removed,
+    Grown,
+    Shrunk,
+    Same,
+}
+
+/// Sizes of the same entity (type, variant or field) in both captures.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub struct SizeDiff {
+    pub old: Option<usize>,
+    pub new: Option<usize>,
+}
+
+impl SizeDiff {
+    pub fn status(&self) -> Status {
+        match (self.old, self.new) {
+            (None, _) => Status::Added,
+            (_, None) => Status::Removed,
+            (Some(old), Some(new)) if new > old => Status::Grown,
+            (Some(old), Some(new)) if new < old => Status::Shrunk,
+            _ => Status::Same,
+        }
+    }
+
+    pub fn delta(&self) -> isize {
+        self.new.unwrap_or(0) as isize - self.old.unwrap_or(0) as isize
+    }
+
+    /// Returns the relative change in percent, if both sizes are known.
+    pub fn percent(&self) -> Option<f64> {
+        match (self.old, self.new) {
+            (Some(0), _) | (None, _) | (_, None) => None,
+            (Some(old), Some(_)) => Some(self.delta() as f64 * 100. / old as f64),
+        }
+    }
+
+    fn max(&self) -> usize {
+        self.old.max(self.new).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct TypeDiff {
+    pub name: String,
+    pub size: SizeDiff,
+    /// Changed fields of a struct.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<FieldDiff>,
+    /// Changed variants of an enum.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<VariantDiff>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct VariantDiff {
+    pub name: String,
+    pub size: SizeDiff,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<FieldDiff>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct FieldDiff {
+    pub name: String,
+    pub kind: FieldKind,
+    pub size: SizeDiff,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_type: Option<String>,
+}
+
+/// Numbers fields with the same name and kind in the order of appearance.
+fn numbered_fields(items: &[FieldOrPadding]) -> Vec<((&str, &FieldKind, usize), &Field)> {
+    let mut seen = HashMap::<(&str, &FieldKind), usize>::new();
+
+    items
+        .iter()
+        .filter_map(|item| match item {
+            FieldOrPadding::Field(f) => Some(f),
+            FieldOrPadding::Padding(_) => None,
+        })
+        .map(|f| {
+            let no = seen.entry((&f.name, &f.kind)).or_default();
+            *no += 1;
+            ((f.name.as_str(), &f.kind, *no), f)
+        })
+        .collect()
+}
+
+/// Matches fields by name and kind. Duplicates (e.g. several locals with the
+/// same name) are matched in the order of appearance.
+fn diff_fields(old: &[FieldOrPadding], new: &[FieldOrPadding], hide_less: usize) -> Vec<FieldDiff> {
+    let old = numbered_fields(old);
+    let new = numbered_fields(new);
+    let old_map = old.iter().cloned().collect::<HashMap<_, _>>();
+    let new_keys = new.iter().map(|(key, _)| key).collect::<HashSet<_>>();
+
+    new.iter()
+        .map(|(key, f)| (key, old_map.get(key).copied(), Some(*f)))
+        .chain(
+            old.iter()
+                .filter(|(key, _)| !new_keys.contains(key))
+                .map(|(key, f)| (key, Some(*f), None)),
+        )
+        .map(|((name, kind, _), old, new)| FieldDiff {
+            name: name.to_string(),
+            kind: (*kind).clone(),
+            size: SizeDiff {
+                old: old.map(|f| f.size),
+                new: new.map(|f| f.size),
+            },
+            local_type: new.or(old).and_then(|f| f.local_type.clone()),
+        })
+        .filter(|d| d.size.status() != Status::Same && d.size.max() >= hide_less)
+        .collect()
+}
+
+/// Splits variants merged by the transformer (e.g. in exported JSON).
+fn split_variants(variants: &[EnumVariant]) -> Vec<(&str, &EnumVariant)> {
+    variants
+        .iter()
+        .flat_map(|v| v.name.split(", ").map(move |name| (name, v)))
+        .collect()
+}
+
+fn diff_variants(old: &[EnumVariant], new: &[EnumVariant], hide_less: usize) -> Vec<VariantDiff> {
+    let old = split_variants(old);
+    let new = split_variants(new);
+    let old_map = old.iter().copied().collect::<HashMap<_, _>>();
+    let new_names = new.iter().map(|(name, _)| name).collect::<HashSet<_>>();
+
+    new.iter()
+        .map(|(name, v)| (name, old_map.get(name).copied(), Some(*v)))
+        .chain(
+            old.iter()
+                .filter(|(name, _)| !new_names.contains(name))
+                .map(|(name, v)| (name, Some(*v), None)),
+        )
+        .map(|(name, old, new)| VariantDiff {
+            name: name.to_string(),
+            size: SizeDiff {
+                old: old.map(|v| v.size),
+                new: new.map(|v| v.size),
+            },
+            items: diff_fields(
+                old.map_or(&[], |v| &v.items),
+                new.map_or(&[], |v| &v.items),
+                hide_less,
+            ),
+        })
+        .filter(|d| d.size.status() != Status::Same || !d.items.is_empty())
+        .collect()
+}
+
+fn struct_items(type_: Option<&Type>) -> &[FieldOrPadding] {
+    match type_.map(|t| &t.kind) {
+        Some(TypeKind::Struct(s)) => &s.items,
+        _ => &[],
+    }
+}
+
+fn enum_variants(type_: Option<&Type>) -> &[EnumVariant] {
+    match type_.map(|t| &t.kind) {
+        Some(TypeKind::Enum(e)) => &e.variants,
+        _ => &[],
+    }
+}
+
+/// Compares layouts of a type. Layouts of added and removed types are omitted.
+fn diff_type(old: Option<&Type>, new: Option<&Type>, hide_less: usize) -> TypeDiff {
+    let (items, variants) = if old.is_some() && new.is_some() {
+        (
+            diff_fields(struct_items(old), struct_items(new), hide_less),
+            diff_variants(enum_variants(old), enum_variants(new), hide_less),
+        )
+    } else {
+        Default::default()
+    };
+
+    TypeDiff {
+        name: new.or(old).map(|t| t.name.clone()).unwrap_or_default(),
+        size: SizeDiff {
+            old: old.map(|t| t.size),
+            new: new.map(|t| t.size),
+        },
+        items,
+        variants,
+    }
+}
+
+/// Keeps the largest type for every name.
+fn index_by_name(types: &[Type]) -> HashMap<&str, &Type> {
+    let mut index = HashMap::<&str, &Type>::with_capacity(types.len());
+
+    for type_ in types {
+        let entry = index.entry(&type_.name).or_insert(type_);
+        if entry.size < type_.size {
+            *entry = type_;
+        }
+    }
+
+    index
+}
+
+/// Compares two captures and returns changed types sorted by the absolute
+/// delta in descending order.
+pub fn diff(old: &[Type], new: &[Type], options: &Options) -> Vec<TypeDiff> {
+    let old = index_by_name(old);
+    let new = index_by_name(new);
+    let hide_less = options.hide_less.unwrap_or(0);
+
+    let names = old.keys().chain(new.keys()).collect::<HashSet<_>>();
+
+    let mut diffs = names
+        .into_iter()
+        .map(|name| (old.get(name).copied(), new.get(name).copied()))
+        .filter(|(old, new)| {
+            old.iter()
+                .chain(new)
+                .any(|t| transformer::is_visible(t, options))
+        })
+        .map(|(old, new)| diff_type(old, new, hide_less))
+        .filter(|d| {
+            d.size.status() != Status::Same || !d.items.is_empty() || !d.variants.is_empty()
+        })
+        .collect::<Vec<_>>();
+
+    diffs.sort_by(|a, b| {
+        (
+            Reverse(a.size.delta().abs()),
+            Reverse(a.size.max()),
+            &a.name,
+        )
+            .cmp(&(
+                Reverse(b.size.delta().abs()),
+                Reverse(b.size.max()),
+                &b.name,
+            ))
+    });
+
+    if let Some(limit) = options.limit {
+        diffs.truncate(limit);
+    }
+
+    diffs
+}
@@ -0,0 +1,138 @@
+use std::cmp::Reverse;
+
+use crate::{
+    held_locals::{state_index, suspend_states},
+    schema::*,
+};
+
+/// Upvars of a coroutine also stored as locals.
+#[derive(Debug)]
+pub struct DuplicatedUpvars<'a> {
+    pub type_: &'a Type,
+    pub upvars: Vec<DuplicatedUpvar<'a>>,
+}
+
+#[derive(Debug)]
+pub struct DuplicatedUpvar<'a> {
+    pub upvar: &'a Field,
+    /// Suspend states holding a copy of the upvar.
+    pub states: Vec<&'a str>,
+}
+
+impl DuplicatedUpvars<'_> {
+    /// Bytes held twice in the worst state.
+    pub fn bytes(&self) -> usize {
+        let states = self.upvars.iter().flat_map(|d| &d.states);
+        let held_in = |state| {
+            let upvars = self.upvars.iter().filter(|d| d.states.contains(state));
+            upvars.map(|d| d.upvar.size).sum()
+        };
+
+        states.map(held_in).max().unwrap_or(0)
+    }
+}
+
+/// Finds upvars of coroutines having a local with the same name and size in
+/// suspend states, e.g. an argument moved into a local before an await.
+///
+/// Coroutines are ranked by duplicated bytes.
+pub fn find(types: &[Type]) -> Vec<DuplicatedUpvars<'_>> {
+    let mut found = types
+        .iter()
+        .filter_map(duplicated_upvars)
+        .filter(|d| !d.upvars.is_empty())
+        .collect::<Vec<_>>();
+
+    found.sort_by_key(|d| Reverse(d.bytes()));
+    found
+}
+
+fn duplicated_upvars(type_: &Type) -> Option<DuplicatedUpvars<'_>> {
+    let TypeKind::Enum(enum_) = &type_.kind else {
+        return None;
+    };
+
+    let mut upvars = Vec::<DuplicatedUpvar<'_>>::new();
+
+    for variant in &enum_.variants {
+        let states = suspend_states(variant).collect::<Vec<_>>();
+        if states.is_empty() {
+            continue;
+        }
+
+        let fields = variant
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                FieldOrPadding::Field(field) => Some(field),
+                FieldOrPadding::Padding(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        let duplicated = fields.iter().filter(|upvar| {
+            upvar.kind == FieldKind::Upvar
+                && fields.iter().any(|local| {
+                    local.kind == FieldKind::GeneratorLocal
+                        && local.name == upvar.name
+                        && local.size == upvar.size
+                })
+        });
+
+        for upvar in duplicated {
+            match upvars.iter_mut().find(|d| d.upvar.name == upvar.name) {
+                Some(d) => d.states.extend(&states),
+                None => upvars.push(DuplicatedUpvar {
+                    upvar,
+                    states: states.clone(),
+                }),
+            }
+        }
+    }
+
+    for upvar in &mut upvars {
+        upvar.states.sort_by_key(|s| state_index(s));
+    }
+
+    upvars.sort_by_key(|d| Reverse(d.upvar.size));
+
+    Some(DuplicatedUpvars { type_, upvars })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_find() {
+        let input = [
+            "type: `{async fn body of f()}`: 48 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `Unresumed`: 24 bytes",
+            "        upvar `.x`: 8 bytes, offset: 0 bytes, alignment: 8 bytes",
+            "        upvar `.y`: 16 bytes, offset: 8 bytes, alignment: 8 bytes",
+            "    variant `Suspend1, Suspend2`: 40 bytes",
+            "        upvar `.x`: 8 bytes, offset: 0 bytes, alignment: 8 bytes",
+            "        upvar `.y`: 16 bytes, offset: 8 bytes, alignment: 8 bytes",
+            "        local `.w`: 8 bytes, alignment: 8 bytes",
+            "        local `.y`: 16 bytes, alignment: 8 bytes",
+            "    variant `Suspend0`: 40 bytes",
+            "        upvar `.x`: 8 bytes, offset: 0 bytes, alignment: 8 bytes",
+            "        upvar `.y`: 16 bytes, offset: 8 bytes, alignment: 8 bytes",
+            "        local `.x`: 8 bytes, alignment: 8 bytes",
+            "        local `.z`: 8 bytes, alignment: 8 bytes",
+        ]
+        .join("\n");
+        let types = parser::parse(&input).unwrap();
+        let found = find(&types);
+
+        assert_eq!(found.len(), 1);
+        // `y` in `Suspend1` and `Suspend2` outweighs `x` in `Suspend0`.
+        assert_eq!(found[0].bytes(), 16);
+        assert_eq!(found[0].upvars.len(), 2);
+        assert_eq!(found[0].upvars[0].upvar.name, "y");
+        assert_eq!(found[0].upvars[0].states, ["Suspend1", "Suspend2"]);
+        assert_eq!(found[0].upvars[1].upvar.name, "x");
+        assert_eq!(found[0].upvars[1].states, ["Suspend0"]);
+    }
+}
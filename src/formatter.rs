@@ -1,22 +1,87 @@
 use std::fmt::Write;
 
-use crate::{options::Options, schema::*};
+use crate::{
+    await_stats,
+    blame::{self, Blame, PartKind},
+    differ::{FieldDiff, SizeDiff, Status, TypeDiff},
+    duplicated_upvars,
+    graph::{EdgeKind, TypeGraph},
+    held_locals, large_variants,
+    options::{ColorChoice, Format, Options},
+    schema::*,
+    waste,
+};
 
-// TODO: colors if TTY
+mod color;
+mod csv;
+mod dot;
+mod folded;
+mod html;
+mod json;
+mod markdown;
+mod parents;
+mod tree;
 
-struct Formatter {
+use self::{
+    color::{Palette, Style},
+    parents::Parents,
+    tree::{Stop, Tree},
+};
+
+#[derive(Default)]
+struct Formatter<'a> {
     o: String,
+    /// Indentation of nested types in the tree view.
+    indent: usize,
+    tree: Option<Tree<'a>>,
+    p: Palette,
 }
 
-impl Formatter {
+impl<'a> Formatter<'a> {
+    fn new(types: &[Type], options: &Options) -> Self {
+        Self {
+            o: String::with_capacity(100 * 1024),
+            p: Palette {
+                enabled: options.color == ColorChoice::Always,
+                max_size: types.iter().map(|t| t.size).max().unwrap_or(0),
+            },
+            ..Self::default()
+        }
+    }
+
+    fn pad(&mut self) {
+        let _ = write!(self.o, "{:1$}", "", self.indent);
+    }
+
+    /// Prints a field's name, highlighting upvars and awaitees.
+    fn format_field_name(&mut self, name: &str, kind: &FieldKind) {
+        let p = self.p;
+
+        if name == "__awaitee" {
+            let _ = write!(self.o, "{}", p.paint(Style::Magenta, name));
+        } else {
+            let _ = write!(self.o, "{name}");
+        }
+
+        if *kind == FieldKind::Upvar {
+            let _ = write!(self.o, " {}", p.paint(Style::Cyan, "(upvar)"));
+        }
+    }
+
+    /// Prints a reference to a field's type.
+    fn format_local_type(&mut self, local_type: &Option<String>) {
+        if let Some(t) = local_type {
+            let _ = write!(self.o, " type={}", self.p.paint(Style::Blue, t));
+        }
+    }
+
     fn format_field_or_padding(&mut self, field: &FieldOrPadding, indent: &str) {
+        self.pad();
+
         match field {
             FieldOrPadding::Field(field) => {
-                let _ = write!(self.o, "{indent}{:>7} {}", field.size, field.name);
-
-                if field.kind == FieldKind::Upvar {
-                    let _ = write!(self.o, " (upvar)");
-                }
+                let _ = write!(self.o, "{indent}{:>7} ", self.p.size(field.size));
+                self.format_field_name(&field.name, &field.kind);
 
                 if let Some(align) = field.align {
                     let _ = write!(self.o, " align={align}");
@@ -24,14 +89,14 @@ impl Formatter {
                 if let Some(offset) = field.offset {
                     let _ = write!(self.o, " offset={offset}");
                 }
-                if let Some(t) = &field.local_type {
-                    let _ = write!(self.o, " type={t}");
-                }
+                self.format_local_type(&field.local_type);
 
                 let _ = writeln!(self.o);
+                self.format_children(field, indent);
             }
             FieldOrPadding::Padding(padding) => {
-                let _ = writeln!(self.o, "{indent}{padding:>7} <padding>");
+                let padding = format!("{padding:>7} <padding>");
+                let _ = writeln!(self.o, "{indent}{}", self.p.paint(Style::Dim, padding));
             }
         }
     }
@@ -44,11 +109,14 @@ impl Formatter {
 
     fn format_enum(&mut self, enum_: &EnumType) {
         if let Some(size) = enum_.discriminant_size {
+            self.pad();
             let _ = writeln!(self.o, "{size:>7} <discriminant>");
         }
 
         for variant in &enum_.variants {
-            let _ = writeln!(self.o, "{:>7} variant {}", variant.size, variant.name);
+            self.pad();
+            let size = self.p.size(variant.size);
+            let _ = writeln!(self.o, "{size:>7} variant {}", variant.name);
 
             if is_wrapping_variant(variant) {
                 continue;
@@ -60,27 +128,490 @@ impl Formatter {
         }
     }
 
+    fn format_header(&mut self, type_: &Type) {
+        self.pad();
+        let p = self.p;
+        let _ = write!(
+            self.o,
+            "{} {} align={}",
+            p.size(type_.size),
+            p.paint(Style::Bold, &type_.name),
+            type_.align
+        );
+
+        if let Some(crate_name) = &type_.crate_name {
+            let _ = write!(self.o, " crate={crate_name}");
+        }
+    }
+
+    fn format_type(&mut self, type_: &Type) {
+        self.format_header(type_);
+        let _ = writeln!(self.o);
+        self.format_body(type_);
+    }
+
+    fn format_body(&mut self, type_: &Type) {
+        match &type_.kind {
+            TypeKind::Struct(s) => self.format_struct(s),
+            TypeKind::Enum(e) => self.format_enum(e),
+        }
+
+        if let Some(padding) = type_.end_padding {
+            self.pad();
+            let padding = format!("{padding:>7} <end padding>");
+            let _ = writeln!(self.o, "{}", self.p.paint(Style::Dim, padding));
+        }
+    }
+
     fn format_types(&mut self, types: &[Type]) {
         for type_ in types {
+            self.format_type(type_);
+            let _ = writeln!(self.o);
+        }
+    }
+
+    /// Prints types as trees, the limit is applied to roots.
+    fn format_tree(&mut self, types: &'a [Type], options: &Options) {
+        let tree = self.tree.as_ref().expect("tree must be set");
+        let roots = tree.roots(options);
+
+        let mut count = 0;
+        for root in roots {
+            if options.limit.is_some_and(|limit| count >= limit) {
+                break;
+            }
+
+            let tree = self.tree.as_mut().expect("tree must be set");
+            if tree.enter(root).is_err() {
+                continue;
+            }
+
+            self.format_type(&types[root]);
+            let _ = writeln!(self.o);
+            count += 1;
+
+            self.tree.as_mut().expect("tree must be set").leave();
+        }
+    }
+
+    /// Prints chains of fields containing types matching `--parents`.
+    fn format_parents(&mut self, types: &'a [Type], options: &Options) {
+        let matched = (0..types.len())
+            .filter(|&id| options.parents.iter().any(|p| p.is_match(&types[id].name)))
+            .take(options.limit.unwrap_or(usize::MAX))
+            .collect::<Vec<_>>();
+
+        let parents = Parents::new(types, matched.iter().copied(), options);
+
+        for id in matched {
+            let type_ = &types[id];
+            self.format_header(type_);
+            let _ = writeln!(self.o);
+
+            let chains = parents.chains(id);
+            if chains.chains.is_empty() {
+                let _ = writeln!(self.o, "    (not contained in other types)");
+            }
+
+            for chain in &chains.chains {
+                let _ = write!(self.o, "   ");
+
+                for link in chain {
+                    let parent = parents.type_(link.parent);
+                    let name = self.p.paint(Style::Bold, &parent.name);
+                    let _ = write!(self.o, " {name} → {}", link.field);
+
+                    if link.kind == EdgeKind::Heuristic {
+                        let _ = write!(self.o, " (by size)");
+                    }
+
+                    let _ = write!(self.o, " →");
+                }
+
+                let _ = writeln!(self.o, " {}", self.p.paint(Style::Bold, &type_.name));
+            }
+
+            if chains.truncated {
+                let _ = writeln!(self.o, "    (more chains omitted)");
+            }
+
+            let _ = writeln!(self.o);
+        }
+    }
+
+    /// Prints ranked breakdowns of types matching `--blame`.
+    fn format_blames(&mut self, types: &[Type], options: &Options) {
+        let graph = TypeGraph::new(types, false);
+
+        let matched = (0..types.len())
+            .filter(|&id| options.blame.iter().any(|p| p.is_match(&types[id].name)))
+            .take(options.limit.unwrap_or(usize::MAX));
+
+        for id in matched {
+            let blame = blame::blame(&graph, id);
+
+            self.format_header(blame.type_);
+            self.format_blame_variant(&blame);
+            let _ = writeln!(self.o);
+
+            self.indent += 4;
+            self.format_blame(&blame, blame.type_.size);
+            self.indent -= 4;
+
+            let _ = writeln!(self.o);
+        }
+    }
+
+    fn format_blame_variant(&mut self, blame: &Blame<'_>) {
+        if let Some(variant) = blame.variant {
+            let _ = write!(self.o, " (variant {})", variant.name);
+        }
+    }
+
+    fn format_blame(&mut self, blame: &Blame<'_>, total: usize) {
+        for part in &blame.parts {
+            self.pad();
+
+            let percent = if total > 0 {
+                part.size as f64 * 100. / total as f64
+            } else {
+                0.
+            };
+            let _ = write!(self.o, "{:>7} {percent:>5.1}% ", self.p.size(part.size));
+
+            let (field, nested) = match &part.kind {
+                PartKind::Field { field, nested } => (field, nested),
+                PartKind::Padding => {
+                    let _ = writeln!(self.o, "{}", self.p.paint(Style::Dim, "<padding>"));
+                    continue;
+                }
+                PartKind::Discriminant => {
+                    let _ = writeln!(self.o, "<discriminant>");
+                    continue;
+                }
+                PartKind::EndPadding => {
+                    let _ = writeln!(self.o, "{}", self.p.paint(Style::Dim, "<end padding>"));
+                    continue;
+                }
+                PartKind::Other => {
+                    let _ = writeln!(self.o, "<other>");
+                    continue;
+                }
+            };
+
+            self.format_field_name(&field.name, &field.kind);
+
+            let Some(nested) = nested else {
+                let _ = writeln!(self.o);
+                continue;
+            };
+
+            let name = self.p.paint(Style::Bold, &nested.type_.name);
+            let _ = write!(self.o, " → {name}");
+            self.format_blame_variant(nested);
+            let _ = writeln!(self.o);
+
+            self.indent += 4;
+            self.format_blame(nested, total);
+            self.indent -= 4;
+        }
+    }
+
+    fn format_large_variants(&mut self, types: &[Type], options: &Options) {
+        let found = large_variants::find(
+            types,
+            options
+                .min_variant_ratio
+                .unwrap_or(large_variants::MIN_RATIO),
+            options
+                .min_variant_bytes
+                .unwrap_or(large_variants::MIN_BYTES),
+        );
+
+        if found.is_empty() {
+            let _ = writeln!(self.o, "no large variants found\n");
+            return;
+        }
+
+        for large in found.iter().take(options.limit.unwrap_or(usize::MAX)) {
+            let p = self.p;
+            self.format_header(large.type_);
+            let saved = format!("-{} bytes", large.savings);
+            let _ = writeln!(self.o, " ({} if boxed)", p.paint(Style::Green, saved));
+
+            let (largest, next) = (large.largest, large.next);
+            let _ = writeln!(
+                self.o,
+                "{:>7} variant {} ({:.1}x)",
+                p.size(largest.size),
+                largest.name,
+                large.ratio()
+            );
+            let _ = writeln!(self.o, "{:>7} variant {}", p.size(next.size), next.name);
+
+            if let Some(field) = large.field {
+                let percent = field.size as f64 * 100. / largest.size as f64;
+                let _ = write!(self.o, "{:>7} {percent:>5.1}% ", p.size(field.size));
+                self.format_field_name(&field.name, &field.kind);
+                self.format_local_type(&field.local_type);
+                let _ = writeln!(self.o);
+            }
+
+            let _ = writeln!(self.o);
+        }
+    }
+
+    fn format_waste(&mut self, types: &[Type], options: &Options) {
+        let found = waste::find(types, options.waste_by);
+
+        for waste in found.iter().take(options.limit.unwrap_or(usize::MAX)) {
+            let _ = write!(self.o, "{:>7} {:>5.1}% ", waste.total(), waste.percent());
+            self.format_header(waste.type_);
+            let _ = writeln!(self.o);
+
+            if waste.internal > 0 {
+                let _ = writeln!(self.o, "{:>7}        <padding>", waste.internal);
+            }
+            if waste.end > 0 {
+                let _ = writeln!(self.o, "{:>7}        <end padding>", waste.end);
+            }
+            for (variant, padding) in &waste.variants {
+                let _ = writeln!(self.o, "{padding:>7}        variant {}", variant.name);
+            }
+
+            let _ = writeln!(self.o);
+        }
+
+        let wasted = found.iter().map(|w| w.total()).sum::<usize>();
+        let size = types.iter().map(|t| t.size).sum::<usize>();
+        let _ = writeln!(
+            self.o,
+            "total: {wasted} bytes of padding in {} of {} types, {:.1}% of {size} bytes\n",
+            found.len(),
+            types.len(),
+            wasted as f64 * 100. / size.max(1) as f64,
+        );
+    }
+
+    fn format_held_locals(&mut self, types: &[Type], options: &Options) {
+        let found = held_locals::find(types);
+
+        if found.is_empty() {
+            let _ = writeln!(self.o, "no held locals found\n");
+            return;
+        }
+
+        for held in found.iter().take(options.limit.unwrap_or(usize::MAX)) {
+            self.format_header(held.type_);
+            let _ = writeln!(self.o);
+
+            // `SuspendN` states are shown as `SN`.
+            let labels = held
+                .states
+                .iter()
+                .map(|s| s.replace("Suspend", "S"))
+                .collect::<Vec<_>>();
+
+            let _ = write!(self.o, "{:>7} {:>7} ", "held", "size");
+            for label in &labels {
+                let _ = write!(self.o, " {label}");
+            }
+            let _ = writeln!(self.o);
+
+            for local in &held.locals {
+                let _ = write!(
+                    self.o,
+                    "{:>7} {:>7} ",
+                    self.p.size(local.cost()),
+                    local.field.size
+                );
+
+                for (i, label) in labels.iter().enumerate() {
+                    let mark = if local.states.contains(&i) { "x" } else { "." };
+                    let _ = write!(self.o, " {mark:>width$}", width = label.len());
+                }
+
+                let _ = write!(self.o, "  ");
+                self.format_field_name(&local.field.name, &local.field.kind);
+                self.format_local_type(&local.field.local_type);
+                let _ = writeln!(self.o);
+            }
+
+            let _ = writeln!(self.o);
+        }
+    }
+
+    fn format_duplicated_upvars(&mut self, types: &[Type], options: &Options) {
+        let found = duplicated_upvars::find(types);
+
+        if found.is_empty() {
+            let _ = writeln!(self.o, "no duplicated upvars found\n");
+            return;
+        }
+
+        for duplicated in found.iter().take(options.limit.unwrap_or(usize::MAX)) {
+            self.format_header(duplicated.type_);
+            let _ = writeln!(self.o, " ({} bytes duplicated)", duplicated.bytes());
+
+            for d in &duplicated.upvars {
+                let _ = write!(self.o, "{:>7} ", self.p.size(d.upvar.size));
+                self.format_field_name(&d.upvar.name, &d.upvar.kind);
+                let _ = writeln!(self.o, " also in {}", d.states.join(", "));
+            }
+
+            let _ = writeln!(self.o);
+        }
+    }
+
+    fn format_await_stats(&mut self, types: &[Type], options: &Options) {
+        let stats = await_stats::collect(types, options.await_stats_by);
+
+        if stats.is_empty() {
+            let _ = writeln!(self.o, "no futures found\n");
+            return;
+        }
+
+        let _ = writeln!(
+            self.o,
+            "{:>7} {:>6} {:>9} {:>9} {:>8}  type",
+            "size", "depth", "suspends", "awaitees", "largest"
+        );
+
+        for s in stats.iter().take(options.limit.unwrap_or(usize::MAX)) {
+            let p = self.p;
             let _ = writeln!(
                 self.o,
-                "{} {} align={}",
-                type_.size, type_.name, type_.align
+                "{:>7} {:>6} {:>9} {:>9} {:>7.1}%  {}",
+                p.size(s.type_.size),
+                s.depth,
+                s.suspends,
+                s.awaitees,
+                s.share(),
+                p.paint(Style::Bold, &s.type_.name)
             );
+        }
 
-            match &type_.kind {
-                TypeKind::Struct(s) => self.format_struct(s),
-                TypeKind::Enum(e) => self.format_enum(e),
+        let _ = writeln!(self.o);
+    }
+
+    /// Prints types contained in the field under it in the tree view.
+    fn format_children(&mut self, field: &Field, indent: &str) {
+        let Some(tree) = &self.tree else {
+            return;
+        };
+
+        let children = tree.children(field);
+        if children.is_empty() {
+            return;
+        }
+
+        // Place children under the field's name.
+        let shift = indent.len() + 8;
+        self.indent += shift;
+
+        for (id, child, kind) in children {
+            self.format_header(child);
+
+            if kind == EdgeKind::Heuristic {
+                let _ = write!(self.o, " (by size)");
+            }
+
+            let tree = self.tree.as_mut().expect("tree must be set");
+            match tree.enter(id) {
+                Ok(()) => {
+                    let _ = writeln!(self.o);
+                    self.format_body(child);
+                    self.tree.as_mut().expect("tree must be set").leave();
+                }
+                Err(stop) => {
+                    let reason = match stop {
+                        Stop::Cycle => "cycle",
+                        Stop::Repeated => "shown above",
+                        Stop::MaxDepth => "max depth",
+                    };
+                    let _ = writeln!(self.o, " ({reason})");
+                }
+            }
+        }
+
+        self.indent -= shift;
+    }
+
+    fn format_size_diff(&mut self, size: &SizeDiff) {
+        match (size.old, size.new) {
+            (None, _) => {
+                let _ = write!(self.o, " (added)");
             }
+            (_, None) => {
+                let _ = write!(self.o, " (removed)");
+            }
+            (Some(old), Some(new)) => {
+                let _ = write!(self.o, " {old} -> {new}");
 
-            if let Some(padding) = type_.end_padding {
-                let _ = writeln!(self.o, "{padding:>7} <end padding>");
+                if let Some(percent) = size.percent() {
+                    let _ = write!(self.o, " ({percent:+.1}%)");
+                }
             }
+        }
+    }
 
+    fn format_field_diff(&mut self, field: &FieldDiff, indent: &str) {
+        let _ = write!(self.o, "{indent}{:>+7} ", self.p.delta(field.size.delta()));
+        self.format_field_name(&field.name, &field.kind);
+        self.format_size_diff(&field.size);
+        self.format_local_type(&field.local_type);
+        let _ = writeln!(self.o);
+    }
+
+    fn format_type_diff(&mut self, diff: &TypeDiff) {
+        let p = self.p;
+        let _ = write!(
+            self.o,
+            "{:+} {}",
+            p.delta(diff.size.delta()),
+            p.paint(Style::Bold, &diff.name)
+        );
+        self.format_size_diff(&diff.size);
+        let _ = writeln!(self.o);
+
+        for item in &diff.items {
+            self.format_field_diff(item, "");
+        }
+
+        for variant in &diff.variants {
+            let _ = write!(
+                self.o,
+                "{:>+7} variant {}",
+                self.p.delta(variant.size.delta()),
+                variant.name
+            );
+            self.format_size_diff(&variant.size);
             let _ = writeln!(self.o);
+
+            for item in &variant.items {
+                self.format_field_diff(item, "    ");
+            }
         }
     }
 
+    fn format_diffs(&mut self, diffs: &[TypeDiff]) {
+        for diff in diffs {
+            self.format_type_diff(diff);
+            let _ = writeln!(self.o);
+        }
+
+        let count = |status| diffs.iter().filter(|d| d.size.status() == status).count();
+        let _ = writeln!(
+            self.o,
+            "{} grown, {} shrunk, {} added, {} removed",
+            count(Status::Grown),
+            count(Status::Shrunk),
+            count(Status::Added),
+            count(Status::Removed),
+        );
+        let _ = writeln!(self.o);
+    }
+
     fn finalize(mut self) -> String {
         // Remove extra trailing `\n`.
         self.o.pop();
@@ -95,18 +626,68 @@ fn is_wrapping_variant(variant: &EnumVariant) -> bool {
 }
 
 pub fn format(mut types: Vec<Type>, options: &Options) -> String {
+    if options.reverse {
+        types.reverse();
+    }
+
+    match options.format {
+        Format::Text => {}
+        Format::Json | Format::Ndjson => return json::format_types(&types, options.format),
+        Format::Dot => return dot::format_types(&types, options),
+        Format::Folded => return folded::format_types(&types),
+        Format::Html => return html::format_types(&types, options),
+        Format::Markdown => return markdown::format_types(&types),
+        Format::Csv => return csv::format_types(&types, options, ','),
+        Format::Tsv => return csv::format_types(&types, options, '\t'),
+    }
+
     if types.is_empty() {
         return "no types found".into();
     }
 
+    let mut formatter = Formatter::new(&types, options);
+
+    if options.is_parents_view() {
+        formatter.format_parents(&types, options);
+    } else if options.is_blame_view() {
+        formatter.format_blames(&types, options);
+    } else if options.is_large_variants_view() {
+        formatter.format_large_variants(&types, options);
+    } else if options.is_waste_view() {
+        formatter.format_waste(&types, options);
+    } else if options.is_held_locals_view() {
+        formatter.format_held_locals(&types, options);
+    } else if options.is_duplicated_upvars_view() {
+        formatter.format_duplicated_upvars(&types, options);
+    } else if options.is_await_stats_view() {
+        formatter.format_await_stats(&types, options);
+    } else if options.is_tree_view() {
+        formatter.tree = Some(Tree::new(&types, options));
+        formatter.format_tree(&types, options);
+    } else {
+        formatter.format_types(&types);
+    }
+
+    formatter.finalize()
+}
+
+pub fn format_diff(mut diffs: Vec<TypeDiff>, options: &Options) -> String {
     if options.reverse {
-        types.reverse();
+        diffs.reverse();
+    }
+
+    match options.format {
+        Format::Text => {}
+        Format::Markdown => return markdown::format_diffs(&diffs),
+        _ => return json::format_diffs(&diffs, options.format),
+    }
+
+    if diffs.is_empty() {
+        return "no changes found".into();
     }
 
-    let mut formatter = Formatter {
-        o: String::with_capacity(100 * 1024),
-    };
+    let mut formatter = Formatter::new(&[], options);
 
-    formatter.format_types(&types);
+    formatter.format_diffs(&diffs);
     formatter.finalize()
 }
@@ -0,0 +1,121 @@
+use std::fmt::{self, Display};
+
+/// ANSI styles of the text format.
+///
+/// Every styled value is reset right after it, so lines stay independent
+/// and can be paged by `less -R`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Style {
+    Bold,
+    Dim,
+    Red,
+    Yellow,
+    Green,
+    Cyan,
+    Magenta,
+    Blue,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Self::Bold => "1",
+            Self::Dim => "2",
+            Self::Red => "31",
+            Self::Yellow => "33",
+            Self::Green => "32",
+            Self::Cyan => "36",
+            Self::Magenta => "35",
+            Self::Blue => "34",
+        }
+    }
+}
+
+/// A value printed with a style if colors are enabled.
+pub(super) struct Painted<T> {
+    style: Option<Style>,
+    value: T,
+}
+
+impl<T: Display> Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(style) = self.style else {
+            return self.value.fmt(f);
+        };
+
+        // Width and other flags are applied to the value.
+        write!(f, "\x1b[{}m", style.code())?;
+        self.value.fmt(f)?;
+        write!(f, "\x1b[0m")
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct Palette {
+    pub(super) enabled: bool,
+    /// The size of the largest type, sizes are colored relative to it.
+    pub(super) max_size: usize,
+}
+
+impl Palette {
+    pub(super) fn paint<T>(self, style: Style, value: T) -> Painted<T> {
+        Painted {
+            style: self.enabled.then_some(style),
+            value,
+        }
+    }
+
+    /// Paints the size by its share of the largest type.
+    pub(super) fn size(self, size: usize) -> Painted<usize> {
+        let style = match size * 100 / self.max_size.max(1) {
+            50.. => Some(Style::Red),
+            25.. => Some(Style::Yellow),
+            10.. => Some(Style::Green),
+            _ => None,
+        };
+
+        Painted {
+            style: style.filter(|_| self.enabled),
+            value: size,
+        }
+    }
+
+    /// Paints the delta of sizes: growth is red, shrinkage is green.
+    pub(super) fn delta(self, delta: isize) -> Painted<isize> {
+        let style = match delta {
+            1.. => Some(Style::Red),
+            ..=-1 => Some(Style::Green),
+            0 => None,
+        };
+
+        Painted {
+            style: style.filter(|_| self.enabled),
+            value: delta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette() {
+        let p = Palette {
+            enabled: true,
+            max_size: 100,
+        };
+
+        assert_eq!(format!("{:>5}", p.size(60)), "\x1b[31m   60\x1b[0m");
+        assert_eq!(format!("{:>5}", p.size(5)), "    5");
+        assert_eq!(format!("{:+}", p.delta(-8)), "\x1b[32m-8\x1b[0m");
+        assert_eq!(format!("{}", p.paint(Style::Bold, "A")), "\x1b[1mA\x1b[0m");
+
+        let p = Palette {
+            enabled: false,
+            ..p
+        };
+        assert_eq!(format!("{:>5}", p.size(60)), "   60");
+        assert_eq!(format!("{}", p.paint(Style::Bold, "A")), "A");
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{
+    options::{Granularity, Options},
+    schema::*,
+};
+
+const TYPE_COLUMNS: &[&str] = &["type", "crate", "size", "align", "kind"];
+const VARIANT_COLUMNS: &[&str] = &["variant", "variant_size"];
+const FIELD_COLUMNS: &[&str] = &[
+    "field",
+    "field_kind",
+    "field_size",
+    "offset",
+    "field_align",
+    "local_type",
+];
+
+/// Rows of a table separated by commas or tabs.
+struct Table {
+    o: String,
+    separator: char,
+}
+
+impl Table {
+    fn row(&mut self, cells: &[String]) {
+        for (i, cell) in cells.iter().enumerate() {
+            if i > 0 {
+                self.o.push(self.separator);
+            }
+
+            if self.separator == '\t' {
+                // TSV has no escaping, so replace special chars.
+                self.o.push_str(&cell.replace(['\t', '\n', '\r'], " "));
+            } else if cell.contains([',', '"', '\n', '\r']) {
+                self.o.push('"');
+                self.o.push_str(&cell.replace('"', "\"\""));
+                self.o.push('"');
+            } else {
+                self.o.push_str(cell);
+            }
+        }
+
+        self.o.push('\n');
+    }
+}
+
+/// Prints types as a table, one row per type, variant or field.
+///
+/// Every row ends with the `padding` column: paddings of the type's largest
+/// variant, of the variant or of the item itself respectively.
+pub(super) fn format_types(types: &[Type], options: &Options, separator: char) -> String {
+    let mut table = Table {
+        o: String::with_capacity(100 * 1024),
+        separator,
+    };
+
+    let mut header = [TYPE_COLUMNS, VARIANT_COLUMNS].concat();
+    if options.granularity == Granularity::Field {
+        header.extend(FIELD_COLUMNS);
+    }
+    header.push("padding");
+    table.row(&header.into_iter().map(String::from).collect::<Vec<_>>());
+
+    for type_ in types {
+        let kind = match &type_.kind {
+            TypeKind::Struct(_) => "struct",
+            TypeKind::Enum(_) => "enum",
+        };
+
+        let row = |variant: Option<&EnumVariant>| {
+            let mut row = vec![
+                type_.name.clone(),
+                type_.crate_name.clone().unwrap_or_default(),
+                type_.size.to_string(),
+                type_.align.to_string(),
+                kind.into(),
+            ];
+            row.extend(match variant {
+                Some(v) => [v.name.clone(), v.size.to_string()],
+                None => Default::default(),
+            });
+            row
+        };
+
+        match (options.granularity, &type_.kind) {
+            (Granularity::Type | Granularity::Variant, TypeKind::Struct(s)) => {
+                let mut row = row(None);
+                row.push((paddings(&s.items) + type_.end_padding.unwrap_or(0)).to_string());
+                table.row(&row);
+            }
+            (Granularity::Type, TypeKind::Enum(e)) => {
+                let variant = e.largest_variant();
+                let items = variant.map_or(&[][..], |v| &v.items[..]);
+
+                let mut row = row(variant);
+                row.push((paddings(items) + type_.end_padding.unwrap_or(0)).to_string());
+                table.row(&row);
+            }
+            (Granularity::Variant, TypeKind::Enum(e)) => {
+                for variant in &e.variants {
+                    let mut row = row(Some(variant));
+                    row.push(paddings(&variant.items).to_string());
+                    table.row(&row);
+                }
+            }
+            (Granularity::Field, TypeKind::Struct(s)) => {
+                for item in &s.items {
+                    let mut row = row(None);
+                    row.extend(item_cells(item));
+                    table.row(&row);
+                }
+            }
+            (Granularity::Field, TypeKind::Enum(e)) => {
+                if let Some(size) = e.discriminant_size {
+                    let mut row = row(None);
+                    row.extend(other_cells("Discriminant", size, 0));
+                    table.row(&row);
+                }
+
+                for variant in &e.variants {
+                    for item in &variant.items {
+                        let mut row = row(Some(variant));
+                        row.extend(item_cells(item));
+                        table.row(&row);
+                    }
+                }
+            }
+        }
+
+        if let (Granularity::Field, Some(size)) = (options.granularity, type_.end_padding) {
+            let mut row = row(None);
+            row.extend(other_cells("EndPadding", size, size));
+            table.row(&row);
+        }
+    }
+
+    // Remove the trailing `\n`.
+    table.o.pop();
+    table.o
+}
+
+/// Returns cells of `FIELD_COLUMNS` and the padding.
+fn item_cells(item: &FieldOrPadding) -> Vec<String> {
+    let field = match item {
+        FieldOrPadding::Field(field) => field,
+        FieldOrPadding::Padding(size) => return other_cells("Padding", *size, *size),
+    };
+
+    let optional = |value: Option<usize>| value.map(|v| v.to_string()).unwrap_or_default();
+    vec![
+        field.name.clone(),
+        format!("{:?}", field.kind),
+        field.size.to_string(),
+        optional(field.offset),
+        optional(field.align),
+        field.local_type.clone().unwrap_or_default(),
+        "0".into(),
+    ]
+}
+
+/// Returns cells of `FIELD_COLUMNS` and the padding for items without names.
+fn other_cells(kind: &str, size: usize, padding: usize) -> Vec<String> {
+    let mut cells = vec![String::new(); FIELD_COLUMNS.len() + 1];
+    cells[1] = kind.into();
+    cells[2] = size.to_string();
+    cells[FIELD_COLUMNS.len()] = padding.to_string();
+    cells
+}
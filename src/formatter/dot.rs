@@ -0,0 +1,62 @@
+use std::fmt::Write;
+
+use crate::{
+    graph::{EdgeKind, TypeGraph},
+    options::Options,
+    schema::*,
+};
+
+/// Prints types as a Graphviz digraph: nodes are types, edges are fields.
+pub(super) fn format_types(types: &[Type], options: &Options) -> String {
+    let graph = TypeGraph::new(types, options.expand_by_size);
+    let mut o = String::with_capacity(100 * 1024);
+
+    let _ = writeln!(o, "digraph types {{");
+    let _ = writeln!(o, "    node [shape=box];");
+
+    for (id, type_) in types.iter().enumerate() {
+        let label = format!("{}\n{} bytes", type_.name, type_.size);
+        let _ = writeln!(o, "    t{id} [label={}];", quote(&label));
+    }
+
+    for id in 0..types.len() {
+        let mut seen = Vec::new();
+
+        for edge in graph.children(id) {
+            // The same field can be in several variants.
+            let key = (edge.child, edge.field.name.as_str(), edge.kind);
+            if seen.contains(&key) {
+                continue;
+            }
+            seen.push(key);
+
+            let _ = write!(
+                o,
+                "    t{} -> t{} [label={}, weight={}",
+                edge.parent,
+                edge.child,
+                quote(&edge.field.name),
+                edge.field.size
+            );
+
+            if edge.kind == EdgeKind::Heuristic {
+                let _ = write!(o, ", style=dashed");
+            }
+
+            let _ = writeln!(o, "];");
+        }
+    }
+
+    let _ = write!(o, "}}");
+    o
+}
+
+/// Makes a DOT string literal.
+fn quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+
+    format!("\"{escaped}\"")
+}
@@ -0,0 +1,68 @@
+use std::fmt::Write;
+
+use crate::{
+    blame::{self, Blame, PartKind},
+    graph::TypeGraph,
+    schema::*,
+};
+
+/// Prints types as folded stacks for flamegraphs: every line is a chain of
+/// frames from a root type to a part of its largest variant and its size.
+pub(super) fn format_types(types: &[Type]) -> String {
+    let graph = TypeGraph::new(types, false);
+    let mut o = String::with_capacity(100 * 1024);
+
+    for id in graph.roots() {
+        let blame = blame::blame(&graph, id);
+        format_blame(&mut o, &mut frame(&blame.type_.name), &blame);
+    }
+
+    // Remove the trailing `\n`.
+    o.pop();
+    o
+}
+
+fn format_blame(o: &mut String, stack: &mut String, blame: &Blame<'_>) {
+    for part in &blame.parts {
+        if part.size == 0 {
+            continue;
+        }
+
+        let len = stack.len();
+        stack.push(';');
+
+        match &part.kind {
+            PartKind::Field {
+                field,
+                nested: Some(nested),
+            } => {
+                stack.push_str(&frame(&field.name));
+                stack.push(':');
+                stack.push_str(&frame(&nested.type_.name));
+                format_blame(o, stack, nested);
+                stack.truncate(len);
+                continue;
+            }
+            PartKind::Field { field, nested: _ } => {
+                stack.push_str(&frame(&field.name));
+
+                // Upvars and locals can have the same name.
+                if field.kind == FieldKind::Upvar {
+                    stack.push_str(" (upvar)");
+                }
+            }
+            PartKind::Padding => stack.push_str("<padding>"),
+            PartKind::Discriminant => stack.push_str("<discriminant>"),
+            PartKind::EndPadding => stack.push_str("<end padding>"),
+            PartKind::Other => stack.push_str("<other>"),
+        }
+
+        let _ = writeln!(o, "{stack} {}", part.size);
+        stack.truncate(len);
+    }
+}
+
+/// Frames are separated by `;`, so replace it in names (e.g. `[u8; 4]`).
+fn frame(name: &str) -> String {
+    name.replace(';', ",")
+}
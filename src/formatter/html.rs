@@ -0,0 +1,216 @@
+use std::fmt::Write;
+
+use regex::Regex;
+
+use crate::{graph::TypeGraph, options::Options, schema::*};
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 0 2em 2em; }
+header { position: sticky; top: 0; background: white; padding: 1em 0; border-bottom: 1px solid #ccc; }
+h1 { font-size: 1.2em; margin: 0 0 0.5em; }
+code, .type { font-family: monospace; }
+#search { width: 40em; max-width: 100%; }
+.type { margin: 0.2em 0; }
+.type:target > summary { background: #ffec99; }
+.body { margin-left: 2em; }
+.variant { margin-left: 0; }
+.item { white-space: pre; }
+.size { display: inline-block; min-width: 4em; text-align: right; margin-right: 0.5em; }
+.note { color: #777; }
+"#;
+
+const SCRIPT: &str = r#"
+const search = document.getElementById("search");
+search.addEventListener("input", () => {
+    const query = search.value.toLowerCase();
+    for (const type of document.querySelectorAll("details.type")) {
+        type.hidden = !type.dataset.name.toLowerCase().includes(query);
+    }
+});
+
+function openTarget() {
+    const target = document.getElementById(location.hash.slice(1));
+    if (target) {
+        target.hidden = false;
+        target.open = true;
+    }
+}
+window.addEventListener("hashchange", openTarget);
+openTarget();
+"#;
+
+/// Prints types as a self-contained HTML page, without external resources.
+pub(super) fn format_types(types: &[Type], options: &Options) -> String {
+    let graph = TypeGraph::new(types, false);
+    let mut o = String::with_capacity(100 * 1024);
+
+    let _ = writeln!(o, "<!DOCTYPE html>");
+    let _ = writeln!(o, "<html lang=\"en\">");
+    let _ = writeln!(o, "<head>");
+    let _ = writeln!(o, "<meta charset=\"utf-8\">");
+    let _ = writeln!(o, "<title>top-type-sizes</title>");
+    let _ = writeln!(o, "<style>{STYLE}</style>");
+    let _ = writeln!(o, "</head>");
+    let _ = writeln!(o, "<body>");
+
+    let _ = writeln!(o, "<header>");
+    let _ = writeln!(o, "<h1>top-type-sizes</h1>");
+    let _ = write!(o, "<p>{} types", types.len());
+    let args = describe_options(options);
+    if !args.is_empty() {
+        let _ = write!(o, ", options: <code>{}</code>", escape(&args.join(" ")));
+    }
+    let _ = writeln!(o, "</p>");
+    let _ = writeln!(
+        o,
+        "<input id=\"search\" type=\"search\" placeholder=\"Search types by name\">"
+    );
+    let _ = writeln!(o, "</header>");
+
+    let _ = writeln!(o, "<main>");
+    for (id, type_) in types.iter().enumerate() {
+        format_type(&mut o, &graph, id, type_);
+    }
+    let _ = writeln!(o, "</main>");
+
+    let _ = writeln!(o, "<script>{SCRIPT}</script>");
+    let _ = writeln!(o, "</body>");
+    let _ = write!(o, "</html>");
+    o
+}
+
+fn format_type(o: &mut String, graph: &TypeGraph<'_>, id: usize, type_: &Type) {
+    let name = escape(&type_.name);
+    let _ = write!(
+        o,
+        "<details class=\"type\" id=\"t{id}\" data-name=\"{name}\"><summary>"
+    );
+    let _ = write!(
+        o,
+        "<span class=\"size\">{}</span>{name} <span class=\"note\">align={}",
+        type_.size, type_.align
+    );
+    if let Some(crate_name) = &type_.crate_name {
+        let _ = write!(o, " crate={}", escape(crate_name));
+    }
+    let _ = writeln!(o, "</span></summary>");
+    let _ = writeln!(o, "<div class=\"body\">");
+
+    match &type_.kind {
+        TypeKind::Struct(s) => format_items(o, graph, &s.items),
+        TypeKind::Enum(e) => {
+            if let Some(size) = e.discriminant_size {
+                format_note(o, size, "&lt;discriminant&gt;");
+            }
+
+            for variant in &e.variants {
+                let _ = write!(o, "<details class=\"variant\" open><summary>");
+                let _ = write!(o, "<span class=\"size\">{}</span>", variant.size);
+                let _ = writeln!(o, "variant {}</summary>", escape(&variant.name));
+                let _ = writeln!(o, "<div class=\"body\">");
+                format_items(o, graph, &variant.items);
+                let _ = writeln!(o, "</div></details>");
+            }
+        }
+    }
+
+    if let Some(padding) = type_.end_padding {
+        format_note(o, padding, "&lt;end padding&gt;");
+    }
+
+    let _ = writeln!(o, "</div></details>");
+}
+
+fn format_items(o: &mut String, graph: &TypeGraph<'_>, items: &[FieldOrPadding]) {
+    for item in items {
+        let field = match item {
+            FieldOrPadding::Field(field) => field,
+            FieldOrPadding::Padding(size) => {
+                format_note(o, *size, "&lt;padding&gt;");
+                continue;
+            }
+        };
+
+        let _ = write!(
+            o,
+            "<div class=\"item\"><span class=\"size\">{}</span>",
+            field.size
+        );
+        let _ = write!(o, "{}", escape(&field.name));
+
+        let mut notes = Vec::new();
+        if field.kind == FieldKind::Upvar {
+            notes.push("(upvar)".into());
+        }
+        if let Some(align) = field.align {
+            notes.push(format!("align={align}"));
+        }
+        if let Some(offset) = field.offset {
+            notes.push(format!("offset={offset}"));
+        }
+        if let Some(local_type) = &field.local_type {
+            // Link to the type's entry if it's shown.
+            notes.push(match graph.find(local_type) {
+                Some(id) => format!("type=<a href=\"#t{id}\">{}</a>", escape(local_type)),
+                None => format!("type={}", escape(local_type)),
+            });
+        }
+
+        let _ = write!(o, " <span class=\"note\">{}", notes.join(" "));
+        let _ = writeln!(o, "</span></div>");
+    }
+}
+
+fn format_note(o: &mut String, size: usize, note: &str) {
+    let _ = writeln!(
+        o,
+        "<div class=\"item\"><span class=\"size\">{size}</span><span class=\"note\">{note}</span></div>"
+    );
+}
+
+/// Describes options affecting shown types to record them in the report.
+fn describe_options(options: &Options) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut patterns = |name: &str, patterns: &[Regex]| {
+        for pattern in patterns {
+            args.push(format!("{name} '{}'", pattern.as_str()));
+        }
+    };
+
+    patterns("--filter", &options.filter);
+    patterns("--exclude", &options.exclude);
+    patterns("--expand", &options.expand);
+    patterns("--crate", &options.crates);
+    patterns("--exclude-crate", &options.exclude_crate);
+    patterns("--parents", &options.parents);
+    patterns("--blame", &options.blame);
+
+    if let Some(hide_less) = options.hide_less {
+        args.push(format!("--hide-less {hide_less}"));
+    }
+    if let Some(limit) = options.limit {
+        args.push(format!("--limit {limit}"));
+    }
+
+    let flags = [
+        ("--remove-wrappers", options.remove_wrappers),
+        ("--sort-fields", options.sort_fields),
+        ("--expand-by-size", options.expand_by_size),
+        ("--reverse", options.reverse),
+    ];
+    for (flag, enabled) in flags {
+        if enabled {
+            args.push(flag.into());
+        }
+    }
+
+    args
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,63 @@
+use serde::Serialize;
+
+use crate::{differ::TypeDiff, options::Format, schema};
+
+#[derive(Serialize)]
+struct Types<'a> {
+    version: u32,
+    types: &'a [schema::Type],
+}
+
+#[derive(Serialize)]
+struct Diffs<'a> {
+    version: u32,
+    diffs: &'a [TypeDiff],
+}
+
+#[derive(Serialize)]
+struct Line<'a, T> {
+    version: u32,
+    #[serde(flatten)]
+    item: &'a T,
+}
+
+fn format_lines<T: Serialize>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| {
+            let line = Line {
+                version: schema::VERSION,
+                item,
+            };
+
+            serde_json::to_string(&line).expect("failed to serialize")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub(super) fn format_types(types: &[schema::Type], format: Format) -> String {
+    if format == Format::Ndjson {
+        return format_lines(types);
+    }
+
+    let document = Types {
+        version: schema::VERSION,
+        types,
+    };
+
+    serde_json::to_string(&document).expect("failed to serialize")
+}
+
+pub(super) fn format_diffs(diffs: &[TypeDiff], format: Format) -> String {
+    if format == Format::Ndjson {
+        return format_lines(diffs);
+    }
+
+    let document = Diffs {
+        version: schema::VERSION,
+        diffs,
+    };
+
+    serde_json::to_string(&document).expect("failed to serialize")
+}
@@ -0,0 +1,227 @@
+use std::fmt::Write;
+
+use super::Formatter;
+use crate::{
+    differ::{SizeDiff, Status, TypeDiff},
+    schema::*,
+};
+
+/// GitHub rejects comments longer than this number of characters.
+const MAX_LEN: usize = 65536;
+/// Reserved for the note about omitted entries.
+const NOTE_LEN: usize = 128;
+
+/// A row of a table and a collapsible section with details.
+struct Entry {
+    row: String,
+    details: String,
+}
+
+/// Prints types as a table with collapsible layouts for PR comments.
+pub(super) fn format_types(types: &[Type]) -> String {
+    let entries = types
+        .iter()
+        .map(|type_| {
+            let variant = match &type_.kind {
+                TypeKind::Enum(e) => e
+                    .largest_variant()
+                    .map(|v| format!("{} ({})", code(&v.name), v.size))
+                    .unwrap_or_default(),
+                TypeKind::Struct(_) => String::new(),
+            };
+
+            let row = format!(
+                "| {} | {} | {} | {variant} |\n",
+                type_.size,
+                type_.align,
+                code(&type_.name)
+            );
+
+            let mut formatter = Formatter::default();
+            formatter.format_type(type_);
+            let title = format!("{} ({} bytes)", type_.name, type_.size);
+
+            Entry {
+                row,
+                details: details(&title, &formatter.o),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let count = fit(&entries, 0);
+
+    let mut o = String::with_capacity(MAX_LEN);
+    let _ = writeln!(o, "| Size | Align | Type | Largest variant |");
+    let _ = writeln!(o, "|-----:|------:|------|-----------------|");
+    format_entries(&mut o, &entries[..count]);
+    format_omitted(&mut o, entries.len() - count, "types");
+
+    o.truncate(o.trim_end().len());
+    o
+}
+
+/// Prints diffs as tables of regressions and improvements for PR comments.
+pub(super) fn format_diffs(diffs: &[TypeDiff]) -> String {
+    let count = |status| diffs.iter().filter(|d| d.size.status() == status).count();
+    let mut o = String::with_capacity(MAX_LEN);
+    let _ = writeln!(
+        o,
+        "**{} grown, {} shrunk, {} added, {} removed**\n",
+        count(Status::Grown),
+        count(Status::Shrunk),
+        count(Status::Added),
+        count(Status::Removed),
+    );
+
+    let sections = [
+        ("Regressions", &[Status::Grown, Status::Added][..]),
+        ("Improvements", &[Status::Shrunk, Status::Removed]),
+        ("Layout changes", &[Status::Same]),
+    ]
+    .map(|(title, statuses)| {
+        let entries = diffs
+            .iter()
+            .filter(|d| statuses.contains(&d.size.status()))
+            .map(diff_entry)
+            .collect::<Vec<_>>();
+        (title, entries)
+    });
+
+    // Every section has a title and a table header.
+    let mut used = o.len() + sections.len() * 128;
+    let mut omitted = 0;
+
+    for (title, entries) in &sections {
+        if entries.is_empty() {
+            continue;
+        }
+
+        let count = fit(entries, used);
+        used += entries[..count]
+            .iter()
+            .map(|e| e.row.len() + e.details.len())
+            .sum::<usize>();
+        omitted += entries.len() - count;
+
+        if count == 0 {
+            continue;
+        }
+
+        let _ = writeln!(o, "### {title}\n");
+        let _ = writeln!(o, "| Old | New | Delta | Type |");
+        let _ = writeln!(o, "|----:|----:|------:|------|");
+        format_entries(&mut o, &entries[..count]);
+    }
+
+    format_omitted(&mut o, omitted, "changes");
+
+    o.truncate(o.trim_end().len());
+    o
+}
+
+fn diff_entry(diff: &TypeDiff) -> Entry {
+    let size = |size: Option<usize>| size.map_or("—".into(), |s| s.to_string());
+    let row = format!(
+        "| {} | {} | {} | {} |\n",
+        size(diff.size.old),
+        size(diff.size.new),
+        delta(&diff.size),
+        code(&diff.name)
+    );
+
+    let mut formatter = Formatter::default();
+    formatter.format_type_diff(diff);
+    let title = format!("{} {}", diff.name, delta(&diff.size));
+
+    Entry {
+        row,
+        details: details(&title, &formatter.o),
+    }
+}
+
+fn delta(size: &SizeDiff) -> String {
+    match size.percent() {
+        Some(percent) => format!("{:+} ({percent:+.1}%)", size.delta()),
+        None => format!("{:+}", size.delta()),
+    }
+}
+
+/// Returns how many entries fit into the limit with `used` characters.
+fn fit(entries: &[Entry], used: usize) -> usize {
+    let mut len = used + NOTE_LEN;
+
+    entries
+        .iter()
+        .take_while(|entry| {
+            len += entry.row.chars().count() + entry.details.chars().count();
+            len <= MAX_LEN
+        })
+        .count()
+}
+
+/// Prints rows of the table, then all details.
+fn format_entries(o: &mut String, entries: &[Entry]) {
+    for entry in entries {
+        o.push_str(&entry.row);
+    }
+
+    let _ = writeln!(o);
+
+    for entry in entries {
+        o.push_str(&entry.details);
+    }
+}
+
+fn format_omitted(o: &mut String, count: usize, what: &str) {
+    if count > 0 {
+        let _ = writeln!(
+            o,
+            "_{count} more {what} omitted because of the comment size limit._"
+        );
+    }
+}
+
+fn details(title: &str, text: &str) -> String {
+    format!(
+        "<details><summary>{}</summary>\n\n```text\n{}\n```\n\n</details>\n\n",
+        escape(title),
+        text.trim_end()
+    )
+}
+
+/// Makes a code span usable in tables.
+fn code(s: &str) -> String {
+    format!("`{}`", s.replace('|', "\\|"))
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_truncation() {
+        let input = (0..2000)
+            .map(|i| {
+                format!(
+                    "type: `Type{i}`: 16 bytes, alignment: 8 bytes\n    field `.{}`: 16 bytes",
+                    "a".repeat(40)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let types = parser::parse(&input).unwrap();
+        let output = format_types(&types);
+
+        assert!(output.chars().count() <= MAX_LEN);
+        let (_, note) = output.rsplit_once('\n').unwrap();
+        assert!(note.starts_with('_') && note.contains("more types omitted"));
+    }
+}
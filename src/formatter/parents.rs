@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use crate::{
+    graph::{EdgeKind, NodeId, TypeGraph},
+    options::Options,
+    schema::*,
+};
+
+/// Chains are enumerated by all paths, so limit them for wide graphs.
+const MAX_CHAINS: usize = 32;
+
+/// A field containing a type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Link<'a> {
+    pub(super) parent: NodeId,
+    pub(super) field: &'a str,
+    pub(super) kind: EdgeKind,
+}
+
+/// Containing types for every type.
+pub(super) struct Parents<'a> {
+    graph: TypeGraph<'a>,
+    /// Types containing the targets, chains are walked only through them.
+    ancestors: HashSet<NodeId>,
+}
+
+/// Chains from roots to the type, every chain starts from a root.
+pub(super) struct Chains<'a> {
+    pub(super) chains: Vec<Vec<Link<'a>>>,
+    /// Set if some chains are omitted because of `MAX_CHAINS`.
+    pub(super) truncated: bool,
+}
+
+impl<'a> Parents<'a> {
+    pub(super) fn new(
+        types: &'a [Type],
+        targets: impl IntoIterator<Item = NodeId>,
+        options: &Options,
+    ) -> Self {
+        let graph = TypeGraph::new(types, options.expand_by_size);
+        let ancestors = graph.ancestors(targets);
+        Self { graph, ancestors }
+    }
+
+    pub(super) fn type_(&self, id: NodeId) -> &'a Type {
+        self.graph.type_(id)
+    }
+
+    /// Enumerates chains depth-first, until `MAX_CHAINS` are found.
+    pub(super) fn chains(&self, target: NodeId) -> Chains<'a> {
+        let mut chains = Chains {
+            chains: Vec::new(),
+            truncated: false,
+        };
+
+        let mut path = Vec::<Link<'a>>::new();
+        let mut on_path = HashSet::new();
+        // Types on the path with their unvisited links and whether they have
+        // parents not on the path.
+        let mut stack = vec![(self.links(target), false)];
+
+        while let Some((links, has_parents)) = stack.last_mut() {
+            // Cycles are possible only if sizes are used.
+            let next = links.find(|link| link.parent != target && !on_path.contains(&link.parent));
+
+            if let Some(link) = next {
+                if chains.chains.len() >= MAX_CHAINS {
+                    chains.truncated = true;
+                    break;
+                }
+
+                *has_parents = true;
+                path.push(link);
+                on_path.insert(link.parent);
+                stack.push((self.links(link.parent), false));
+                continue;
+            }
+
+            if !*has_parents && !path.is_empty() {
+                chains.chains.push(path.iter().rev().copied().collect());
+            }
+
+            stack.pop();
+            if let Some(link) = path.pop() {
+                on_path.remove(&link.parent);
+            }
+        }
+
+        // Show the most direct chains first.
+        chains.chains.sort_by_key(Vec::len);
+        chains
+    }
+
+    /// Returns fields containing the type.
+    fn links(&self, id: NodeId) -> impl Iterator<Item = Link<'a>> + '_ {
+        // The same field can be in several variants, they are adjacent.
+        let mut prev = Vec::<Link<'a>>::new();
+
+        self.graph
+            .parents(id)
+            .filter(|edge| self.ancestors.contains(&edge.parent))
+            .filter_map(move |edge| {
+                let link = Link {
+                    parent: edge.parent,
+                    field: &edge.field.name,
+                    kind: edge.kind,
+                };
+
+                if prev.first().is_some_and(|p| p.parent != link.parent) {
+                    prev.clear();
+                }
+
+                if prev.contains(&link) {
+                    return None;
+                }
+
+                prev.push(link);
+                Some(link)
+            })
+    }
+}
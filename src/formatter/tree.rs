@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+
+use crate::{
+    graph::{EdgeKind, NodeId, TypeGraph},
+    options::Options,
+    schema::*,
+};
+
+/// State of the tree view.
+pub(super) struct Tree<'a> {
+    graph: TypeGraph<'a>,
+    max_depth: Option<usize>,
+    /// Types from the root to the current one.
+    path: Vec<NodeId>,
+    /// The same types as `path`, to detect cycles.
+    on_path: HashSet<NodeId>,
+    /// Types already expanded somewhere.
+    expanded: HashSet<NodeId>,
+}
+
+/// Why a child type isn't expanded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Stop {
+    Cycle,
+    Repeated,
+    MaxDepth,
+}
+
+impl<'a> Tree<'a> {
+    pub(super) fn new(types: &'a [Type], options: &Options) -> Self {
+        Self {
+            graph: TypeGraph::new(types, options.expand_by_size),
+            max_depth: options.tree_depth,
+            path: Vec::new(),
+            on_path: HashSet::new(),
+            expanded: HashSet::new(),
+        }
+    }
+
+    /// Returns types contained in the field of the current type.
+    pub(super) fn children(&self, field: &Field) -> Vec<(NodeId, &'a Type, EdgeKind)> {
+        let Some(&parent) = self.path.last() else {
+            return Vec::new();
+        };
+
+        let Some(index) = self.graph.field_index(parent, field) else {
+            return Vec::new();
+        };
+
+        self.graph
+            .field_edges(index)
+            .map(|edge| (edge.child, self.graph.type_(edge.child), edge.kind))
+            .collect()
+    }
+
+    /// Returns types that should be printed as roots, all other types are
+    /// reachable from them unless filtered out.
+    pub(super) fn roots(&self, options: &Options) -> Vec<NodeId> {
+        let types = self.graph.types();
+        let is_root = |id: &NodeId| {
+            if options.expand.is_empty() {
+                // Types not contained in other types.
+                self.graph.parents(*id).next().is_none()
+            } else {
+                options.expand.iter().any(|p| p.is_match(&types[*id].name))
+            }
+        };
+
+        // Other types are appended to show types in cycles or containing
+        // filtered out fields.
+        let (mut roots, others): (Vec<_>, Vec<_>) = (0..types.len()).partition(is_root);
+        roots.extend(others);
+        roots
+    }
+
+    /// Starts expanding the type if possible.
+    pub(super) fn enter(&mut self, id: NodeId) -> Result<(), Stop> {
+        if self.on_path.contains(&id) {
+            return Err(Stop::Cycle);
+        }
+
+        if self.expanded.contains(&id) {
+            return Err(Stop::Repeated);
+        }
+
+        // The root has depth 0.
+        if self
+            .max_depth
+            .is_some_and(|max_depth| self.path.len() > max_depth)
+        {
+            return Err(Stop::MaxDepth);
+        }
+
+        self.path.push(id);
+        self.on_path.insert(id);
+        self.expanded.insert(id);
+        Ok(())
+    }
+
+    pub(super) fn leave(&mut self) {
+        if let Some(id) = self.path.pop() {
+            self.on_path.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use structopt::StructOpt;
+
+    use crate::{formatter, options::Options, parser};
+
+    #[test]
+    fn test_cycle() {
+        let input = [
+            "type: `A`: 8 bytes, alignment: 8 bytes",
+            "    field `.b`: 4 bytes",
+            "    field `.c`: 4 bytes",
+            "type: `B`: 4 bytes, alignment: 4 bytes",
+            "    field `.c`: 4 bytes",
+            "type: `C`: 4 bytes, alignment: 4 bytes",
+            "    field `.b`: 4 bytes",
+        ]
+        .join("\n");
+
+        let types = parser::parse(&input).unwrap();
+        let options = Options::from_iter(["top-type-sizes", "--tree", "--expand-by-size"]);
+        let output = formatter::format(types, &options);
+
+        let expected = [
+            "8 A align=8",
+            "      4 b",
+            "        4 B align=4 (by size)",
+            "              4 c",
+            "                4 C align=4 (by size)",
+            "                      4 b",
+            "                        4 B align=4 (by size) (cycle)",
+            "        4 C align=4 (by size) (shown above)",
+            "      4 c",
+            "        4 B align=4 (by size) (shown above)",
+            "        4 C align=4 (by size) (shown above)",
+        ];
+        assert_eq!(output, expected.join("\n"));
+    }
+}
@@ -0,0 +1,394 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use crate::schema::*;
+
+/// An index of a type in the slice the graph is built from.
+pub type NodeId = usize;
+
+/// How a child type is matched to a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    /// By the field's type name, provided by the compiler.
+    Exact,
+    /// By the field's size and alignment (if provided), can be wrong.
+    Heuristic,
+}
+
+/// A field of the parent type containing the child type.
+#[derive(Debug, Clone, Copy)]
+pub struct Edge<'a> {
+    pub parent: NodeId,
+    pub child: NodeId,
+    pub field: &'a Field,
+    /// A variant containing the field, if the parent is an enum.
+    pub variant: Option<&'a str>,
+    pub kind: EdgeKind,
+}
+
+/// A containment graph of types: nodes are types, edges are fields.
+///
+/// Types with the same name (e.g. laid out in different crates) are
+/// different nodes, but edges by name lead to the first one.
+///
+/// Only edges by name are stored. Edges by size are found on demand, because
+/// every field would be connected to every type of the same size.
+#[derive(Debug)]
+pub struct TypeGraph<'a> {
+    types: &'a [Type],
+    by_name: HashMap<&'a str, NodeId>,
+    by_size: HashMap<usize, Vec<NodeId>>,
+    /// All fields of all types, in order.
+    fields: Vec<FieldRef<'a>>,
+    /// Indices of fields of every type.
+    fields_of: Vec<Range<usize>>,
+    /// Indices of fields with type names (resolved to existing types) by
+    /// their types.
+    exact_parents: Vec<Vec<usize>>,
+    /// Indices of fields without type names by their sizes.
+    sized_fields: HashMap<usize, Vec<usize>>,
+}
+
+#[derive(Debug)]
+struct FieldRef<'a> {
+    parent: NodeId,
+    field: &'a Field,
+    variant: Option<&'a str>,
+    /// The type of the field, if its name is provided and known.
+    child: Option<NodeId>,
+}
+
+impl<'a> TypeGraph<'a> {
+    /// Builds the graph. If `use_size` is true, heuristic edges are added for
+    /// fields without type names.
+    pub fn new(types: &'a [Type], use_size: bool) -> Self {
+        let mut by_name = HashMap::with_capacity(types.len());
+        let mut by_size = HashMap::<usize, Vec<NodeId>>::new();
+
+        for (id, type_) in types.iter().enumerate() {
+            by_name.entry(type_.name.as_str()).or_insert(id);
+
+            if use_size {
+                by_size.entry(type_.size).or_default().push(id);
+            }
+        }
+
+        let mut graph = Self {
+            types,
+            by_name,
+            by_size,
+            fields: Vec::new(),
+            fields_of: Vec::with_capacity(types.len()),
+            exact_parents: vec![Vec::new(); types.len()],
+            sized_fields: HashMap::new(),
+        };
+
+        for (parent, type_) in types.iter().enumerate() {
+            let start = graph.fields.len();
+
+            for (variant, field) in fields_with_variants(type_) {
+                let index = graph.fields.len();
+                let child = match &field.local_type {
+                    Some(name) => graph.by_name.get(name.as_str()).copied(),
+                    None => {
+                        if use_size {
+                            graph
+                                .sized_fields
+                                .entry(field.size)
+                                .or_default()
+                                .push(index);
+                        }
+                        None
+                    }
+                };
+
+                if let Some(child) = child.filter(|&child| child != parent) {
+                    graph.exact_parents[child].push(index);
+                }
+
+                graph.fields.push(FieldRef {
+                    parent,
+                    field,
+                    variant,
+                    child,
+                });
+            }
+
+            graph.fields_of.push(start..graph.fields.len());
+        }
+
+        graph
+    }
+
+    fn edge(&self, index: usize, child: NodeId) -> Edge<'a> {
+        let field = &self.fields[index];
+
+        Edge {
+            parent: field.parent,
+            child,
+            field: field.field,
+            variant: field.variant,
+            kind: match field.field.local_type {
+                Some(_) => EdgeKind::Exact,
+                None => EdgeKind::Heuristic,
+            },
+        }
+    }
+
+    pub fn types(&self) -> &'a [Type] {
+        self.types
+    }
+
+    pub fn type_(&self, id: NodeId) -> &'a Type {
+        &self.types[id]
+    }
+
+    /// Returns the first type with the name.
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Returns fields of the type containing other types.
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = Edge<'a>> + '_ {
+        self.fields_of[id]
+            .clone()
+            .flat_map(move |index| self.field_edges(index))
+    }
+
+    /// Returns the index of the type's field, which is one of its items.
+    pub fn field_index(&self, id: NodeId, field: &Field) -> Option<usize> {
+        self.fields_of[id]
+            .clone()
+            .find(|&index| std::ptr::eq(self.fields[index].field, field))
+    }
+
+    /// Returns types contained in the field with the index.
+    pub fn field_edges(&self, index: usize) -> impl Iterator<Item = Edge<'a>> + '_ {
+        let parent = self.fields[index].parent;
+
+        self.field_children(index)
+            // Wrappers have fields of their own size.
+            .filter(move |&child| child != parent)
+            .map(move |child| self.edge(index, child))
+    }
+
+    /// Returns fields of other types containing the type.
+    pub fn parents(&self, id: NodeId) -> impl Iterator<Item = Edge<'a>> + '_ {
+        let mut exact = self.exact_parents[id].iter().copied().peekable();
+        let mut heuristic = self
+            .size_parents(self.type_(id))
+            .filter(move |&index| self.fields[index].parent != id)
+            .peekable();
+
+        // Keep the order of fields.
+        let indices = std::iter::from_fn(move || match (exact.peek(), heuristic.peek()) {
+            (Some(a), Some(b)) if a > b => heuristic.next(),
+            (Some(_), _) => exact.next(),
+            (None, _) => heuristic.next(),
+        });
+
+        indices.map(move |index| self.edge(index, id))
+    }
+
+    /// Returns types not contained in other types, e.g. root futures.
+    pub fn roots(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.types.len()).filter(|&id| self.parents(id).next().is_none())
+    }
+
+    /// Returns the types and all types contained in them.
+    pub fn descendants(&self, ids: impl IntoIterator<Item = NodeId>) -> HashSet<NodeId> {
+        let mut visited = HashSet::new();
+        let mut expanded_sizes = HashSet::new();
+        let mut queue = ids.into_iter().collect::<Vec<_>>();
+
+        while let Some(id) = queue.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+
+            for index in self.fields_of[id].clone() {
+                let field = self.fields[index].field;
+
+                // All fields of the same size and alignment lead to the same
+                // types, so expand them once.
+                if field.local_type.is_some() || expanded_sizes.insert((field.size, field.align)) {
+                    queue.extend(self.field_children(index));
+                }
+            }
+        }
+
+        visited
+    }
+
+    /// Returns the types and all types containing them.
+    pub fn ancestors(&self, ids: impl IntoIterator<Item = NodeId>) -> HashSet<NodeId> {
+        let mut visited = HashSet::new();
+        let mut expanded_sizes = HashSet::new();
+        let mut queue = ids.into_iter().collect::<Vec<_>>();
+
+        while let Some(id) = queue.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+
+            let type_ = self.type_(id);
+            let parents = self.exact_parents[id]
+                .iter()
+                .map(|&index| self.fields[index].parent);
+            queue.extend(parents);
+
+            // Types of the same size and alignment are contained in the same
+            // fields, so expand them once.
+            if expanded_sizes.insert((type_.size, type_.align)) {
+                let parents = self
+                    .size_parents(type_)
+                    .map(|index| self.fields[index].parent);
+                queue.extend(parents);
+            }
+        }
+
+        visited
+    }
+
+    /// Checks if the `to` type is contained in the `from` type, directly or
+    /// transitively.
+    pub fn is_reachable(&self, from: NodeId, to: NodeId) -> bool {
+        from != to && self.descendants([from]).contains(&to)
+    }
+
+    /// Returns types contained in the field, including the parent itself.
+    fn field_children(&self, index: usize) -> impl Iterator<Item = NodeId> + '_ {
+        let FieldRef { field, child, .. } = &self.fields[index];
+
+        let by_size = match field.local_type {
+            Some(_) => None,
+            None => self.by_size.get(&field.size),
+        };
+
+        let by_size = by_size.into_iter().flatten().copied();
+        let by_size =
+            by_size.filter(|&child| field.align.is_none_or(|a| a == self.types[child].align));
+        child.iter().copied().chain(by_size)
+    }
+
+    /// Returns indices of fields without type names matching the type by size,
+    /// including fields of the type itself.
+    fn size_parents(&self, type_: &'a Type) -> impl Iterator<Item = usize> + '_ {
+        let fields = self.sized_fields.get(&type_.size);
+        fields.into_iter().flatten().copied().filter(move |&index| {
+            self.fields[index]
+                .field
+                .align
+                .is_none_or(|a| a == type_.align)
+        })
+    }
+}
+
+fn fields_with_variants(type_: &Type) -> Vec<(Option<&str>, &Field)> {
+    match &type_.kind {
+        TypeKind::Struct(s) => with_variant(None, &s.items).collect(),
+        TypeKind::Enum(e) => e
+            .variants
+            .iter()
+            .flat_map(|v| with_variant(Some(&v.name), &v.items))
+            .collect(),
+    }
+}
+
+fn with_variant<'a>(
+    variant: Option<&'a str>,
+    items: &'a [FieldOrPadding],
+) -> impl Iterator<Item = (Option<&'a str>, &'a Field)> {
+    items.iter().filter_map(move |item| match item {
+        FieldOrPadding::Field(field) => Some((variant, field)),
+        FieldOrPadding::Padding(_) => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_graph() {
+        let input = [
+            "type: `{async fn body of main()}`: 24 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `Suspend0`: 23 bytes",
+            "        padding: 7 bytes",
+            "        local `.__awaitee`: 16 bytes, alignment: 8 bytes, type: {async fn body of serve()}",
+            "type: `{async fn body of serve()}`: 16 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `Suspend0`: 15 bytes",
+            "        padding: 3 bytes",
+            "        local `.conn`: 4 bytes, alignment: 4 bytes",
+            "        local `.timer`: 8 bytes, alignment: 8 bytes",
+            "type: `Conn`: 4 bytes, alignment: 4 bytes",
+            "    field `.fd`: 4 bytes",
+            "type: `Timer`: 8 bytes, alignment: 8 bytes",
+            "    field `.deadline`: 8 bytes",
+            "type: `Flags`: 4 bytes, alignment: 2 bytes",
+            "    field `.0`: 4 bytes",
+        ]
+        .join("\n");
+
+        let types = parser::parse(&input).unwrap();
+        let graph = TypeGraph::new(&types, false);
+        let id = |name| graph.find(name).unwrap();
+
+        let main = id("{async fn body of main()}");
+        let serve = id("{async fn body of serve()}");
+
+        let children = graph.children(main).collect::<Vec<_>>();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].child, serve);
+        assert_eq!(children[0].field.name, "__awaitee");
+        assert_eq!(children[0].variant, Some("Suspend0"));
+        assert_eq!(children[0].kind, EdgeKind::Exact);
+
+        assert_eq!(graph.children(serve).count(), 0);
+        assert!(graph.is_reachable(main, serve));
+        assert!(!graph.is_reachable(serve, main));
+
+        // By size and alignment, `Flags` has different alignment.
+        let graph = TypeGraph::new(&types, true);
+        let children = graph
+            .children(serve)
+            .map(|e| (graph.type_(e.child).name.as_str(), e.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            children,
+            [
+                ("Conn", EdgeKind::Heuristic),
+                ("Timer", EdgeKind::Heuristic)
+            ]
+        );
+
+        let roots = graph.roots().collect::<Vec<_>>();
+        assert_eq!(roots, [main]);
+
+        let ancestors = graph.ancestors([id("Timer")]);
+        assert_eq!(ancestors, HashSet::from([main, serve, id("Timer")]));
+    }
+
+    #[test]
+    fn test_many_types_of_same_size() {
+        // Every type contains all others by size, edges aren't stored.
+        let input = (0..5000)
+            .map(|i| format!("type: `T{i}`: 8 bytes, alignment: 8 bytes\n    field `.0`: 8 bytes"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let types = parser::parse(&input).unwrap();
+        let graph = TypeGraph::new(&types, true);
+
+        assert_eq!(graph.children(0).count(), 4999);
+        assert_eq!(graph.parents(0).count(), 4999);
+        assert_eq!(graph.roots().count(), 0);
+        assert_eq!(graph.descendants([0]).len(), 5000);
+        assert_eq!(graph.ancestors([0]).len(), 5000);
+    }
+}
@@ -0,0 +1,169 @@
+use std::cmp::Reverse;
+
+use crate::schema::*;
+
+/// Locals of a coroutine and its suspend states holding them.
+#[derive(Debug)]
+pub struct HeldLocals<'a> {
+    pub type_: &'a Type,
+    /// Names of suspend states, merged variants are split.
+    pub states: Vec<&'a str>,
+    /// Locals ranked by bytes held over all states.
+    pub locals: Vec<HeldLocal<'a>>,
+}
+
+#[derive(Debug)]
+pub struct HeldLocal<'a> {
+    pub field: &'a Field,
+    /// Indices of states holding the local.
+    pub states: Vec<usize>,
+}
+
+impl HeldLocal<'_> {
+    /// Bytes held over all states.
+    pub fn cost(&self) -> usize {
+        self.field.size * self.states.len()
+    }
+}
+
+/// Builds matrices of locals and suspend states of coroutines.
+///
+/// Locals are matched by name, size and type, so different locals with the
+/// same name aren't mixed up. Coroutines are ranked by the cost of their top
+/// local.
+pub fn find(types: &[Type]) -> Vec<HeldLocals<'_>> {
+    let mut found = types
+        .iter()
+        .filter_map(held_locals)
+        .filter(|h| !h.locals.is_empty())
+        .collect::<Vec<_>>();
+
+    found.sort_by_key(|h| Reverse(h.locals[0].cost()));
+    found
+}
+
+fn held_locals(type_: &Type) -> Option<HeldLocals<'_>> {
+    let TypeKind::Enum(enum_) = &type_.kind else {
+        return None;
+    };
+
+    let mut states = Vec::new();
+    let mut locals = Vec::<HeldLocal<'_>>::new();
+
+    for variant in &enum_.variants {
+        let first_state = states.len();
+        states.extend(suspend_states(variant));
+        let variant_states = first_state..states.len();
+
+        let fields = variant.items.iter().filter_map(|item| match item {
+            FieldOrPadding::Field(f) if f.kind == FieldKind::GeneratorLocal => Some(f),
+            _ => None,
+        });
+
+        for field in fields {
+            let local = match locals.iter_mut().find(|l| is_same_local(l.field, field)) {
+                Some(local) => local,
+                None => {
+                    locals.push(HeldLocal {
+                        field,
+                        states: Vec::new(),
+                    });
+                    locals.last_mut().unwrap()
+                }
+            };
+
+            local.states.extend(variant_states.clone());
+        }
+    }
+
+    // Show states in their order.
+    let mut order = (0..states.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| state_index(states[i]));
+    let mut positions = vec![0; states.len()];
+    for (position, &i) in order.iter().enumerate() {
+        positions[i] = position;
+    }
+
+    for local in &mut locals {
+        for state in &mut local.states {
+            *state = positions[*state];
+        }
+        local.states.sort_unstable();
+        local.states.dedup();
+    }
+
+    locals.sort_by_key(|l| Reverse(l.cost()));
+
+    Some(HeldLocals {
+        type_,
+        states: order.into_iter().map(|i| states[i]).collect(),
+        locals,
+    })
+}
+
+/// Returns names of suspend states of the variant, merged ones are split.
+pub(crate) fn suspend_states(variant: &EnumVariant) -> impl Iterator<Item = &str> {
+    variant
+        .name
+        .split(", ")
+        .filter(|name| name.starts_with("Suspend"))
+}
+
+fn is_same_local(a: &Field, b: &Field) -> bool {
+    a.name == b.name && a.size == b.size && a.local_type == b.local_type
+}
+
+/// Returns `N` of `SuspendN` to sort states numerically.
+pub(crate) fn state_index(name: &str) -> (Option<usize>, &str) {
+    (
+        name.strip_prefix("Suspend").and_then(|n| n.parse().ok()),
+        name,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_find() {
+        let input = [
+            "type: `{async fn body of f()}`: 48 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `Unresumed`: 8 bytes",
+            "        upvar `.x`: 8 bytes, offset: 0 bytes, alignment: 8 bytes",
+            "    variant `Suspend1, Suspend2`: 40 bytes",
+            "        upvar `.x`: 8 bytes, offset: 0 bytes, alignment: 8 bytes",
+            "        local `.buf`: 16 bytes, alignment: 8 bytes",
+            "        local `.__awaitee`: 16 bytes, alignment: 8 bytes, type: {async fn body of g()}",
+            "    variant `Suspend0`: 32 bytes",
+            "        upvar `.x`: 8 bytes, offset: 0 bytes, alignment: 8 bytes",
+            "        local `.buf`: 16 bytes, alignment: 8 bytes",
+            "        local `.__awaitee`: 8 bytes, alignment: 8 bytes, type: {async fn body of h()}",
+            "type: `S`: 8 bytes, alignment: 8 bytes",
+            "    field `.0`: 8 bytes",
+        ]
+        .join("\n");
+        let types = parser::parse(&input).unwrap();
+        let found = find(&types);
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].states, ["Suspend0", "Suspend1", "Suspend2"]);
+
+        let locals = found[0]
+            .locals
+            .iter()
+            .map(|l| (l.field.name.as_str(), l.field.size, &l.states[..], l.cost()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            locals,
+            [
+                ("buf", 16, &[0, 1, 2][..], 48),
+                ("__awaitee", 16, &[1, 2][..], 32),
+                ("__awaitee", 8, &[0][..], 8),
+            ]
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+
+use crate::{
+    parser::Skipped,
+    schema::*,
+    validator::{self, Issue, Severity},
+};
+
+/// A type that is likely broken by interleaved output of a parallel build.
+#[derive(Debug, Clone)]
+pub struct Quarantined {
+    pub type_: Type,
+    pub reason: Reason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    /// The layout is inconsistent (see the `validator` module).
+    Invalid(Issue),
+    /// An enum is followed by an unparsable region, so some of its variants
+    /// may be lost. Unlike structs, it cannot be verified by sizes.
+    Truncated,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(issue) => issue.fmt(f),
+            Self::Truncated => write!(f, "followed by an unparsable region"),
+        }
+    }
+}
+
+/// Moves types which layouts are inconsistent (have validation errors)
+/// because of interleaved lines out of `types`.
+///
+/// Must be applied only to the compiler's output, because exported JSON can
+/// contain partial layouts (e.g. after `-h` or `-s`).
+pub fn quarantine(types: &mut Vec<Type>, skipped: &[Skipped]) -> Vec<Quarantined> {
+    let mut reasons = types.iter().map(check).collect::<Vec<_>>();
+
+    for index in skipped.iter().filter_map(|s| s.preceded_by) {
+        if reasons[index].is_none() && matches!(types[index].kind, TypeKind::Enum(_)) {
+            reasons[index] = Some(Reason::Truncated);
+        }
+    }
+
+    let mut quarantined = Vec::new();
+    let mut reasons = reasons.into_iter();
+
+    types.retain(|type_| match reasons.next().flatten() {
+        Some(reason) => {
+            quarantined.push(Quarantined {
+                type_: type_.clone(),
+                reason,
+            });
+            false
+        }
+        None => true,
+    });
+
+    quarantined
+}
+
+fn check(type_: &Type) -> Option<Reason> {
+    validator::validate(type_)
+        .into_iter()
+        .find(|issue| issue.severity() == Severity::Error)
+        .map(Reason::Invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_quarantine() {
+        // `A` is interrupted by `B`, then the rest of `A` is attributed to `B`.
+        let input = [
+            "type: `A`: 16 bytes, alignment: 8 bytes",
+            "    field `.a`: 8 bytes",
+            "type: `B`: 4 bytes, alignment: 4 bytes",
+            "    field `.b`: 4 bytes",
+            "    field `.c`: 8 bytes",
+            "type: `C`: 8 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `X`: 7 bytes",
+            "        field `.0`: 4 bytes",
+            "        field `.1`: 8 bytes",
+            "type: `D`: 8 bytes, alignment: 4 bytes",
+            "    discriminant: 4 bytes",
+            "type: `E`: 8 bytes, alignment: 4 bytes",
+            "    discriminant: 4 bytes",
+            "    variant `X`: 4 bytes",
+            "            field `.0`: 4 bytes",
+            "type: `F`: 16 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `X`: 15 bytes",
+            "        field `.0`: 1 bytes",
+            "type: `G`: 4 bytes, alignment: 4 bytes",
+            "    field `.g`: 4 bytes",
+        ]
+        .join("\n");
+
+        let (mut types, skipped) = parser::parse_lenient(&input);
+        let quarantined = quarantine(&mut types, &skipped);
+
+        let names = types.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["G"]);
+
+        let reasons = quarantined
+            .iter()
+            .map(|q| (q.type_.name.as_str(), q.reason.clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            reasons,
+            [
+                ("A", Reason::Invalid(Issue::SizeMismatch { items_size: 8 })),
+                ("B", Reason::Invalid(Issue::SizeMismatch { items_size: 12 })),
+                (
+                    "C",
+                    Reason::Invalid(Issue::LargeVariant {
+                        variant: "X".into(),
+                        size: 12
+                    })
+                ),
+                ("D", Reason::Invalid(Issue::NoVariants)),
+                ("E", Reason::Truncated),
+                (
+                    "F",
+                    Reason::Invalid(Issue::VariantSizeMismatch {
+                        variant: "X".into(),
+                        items_size: 1
+                    })
+                ),
+            ]
+        );
+    }
+}
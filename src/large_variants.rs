@@ -0,0 +1,151 @@
+use std::cmp::Reverse;
+
+use crate::schema::*;
+
+/// The size of `Box<T>` for sized `T`, assuming 64-bit targets.
+pub const POINTER_SIZE: usize = 8;
+
+/// The default of `--min-variant-ratio`.
+pub const MIN_RATIO: f64 = 2.;
+
+/// The default of `--min-variant-bytes`.
+pub const MIN_BYTES: usize = 64;
+
+/// An enum whose largest variant is much bigger than the next one.
+#[derive(Debug)]
+pub struct LargeVariant<'a> {
+    pub type_: &'a Type,
+    pub largest: &'a EnumVariant,
+    /// The second largest variant.
+    pub next: &'a EnumVariant,
+    /// The largest field of the largest variant, except upvars.
+    pub field: Option<&'a Field>,
+    /// An estimate of bytes saved if the largest variant's payload is boxed.
+    pub savings: usize,
+}
+
+impl LargeVariant<'_> {
+    /// How many times the largest variant is bigger than the next one.
+    pub fn ratio(&self) -> f64 {
+        self.largest.size as f64 / self.next.size.max(1) as f64
+    }
+}
+
+/// Finds enums, including coroutines, whose largest variant is at least
+/// `min_ratio` times and `min_bytes` bytes bigger than the next one.
+///
+/// Upvars of coroutines are stored in every variant, so only locals are
+/// considered as the boxed payload. Results are ranked by savings.
+pub fn find(types: &[Type], min_ratio: f64, min_bytes: usize) -> Vec<LargeVariant<'_>> {
+    let mut found = types
+        .iter()
+        .filter_map(|type_| check(type_, min_ratio, min_bytes))
+        .collect::<Vec<_>>();
+
+    // Use stable sort to keep larger types first.
+    found.sort_by_key(|v| Reverse(v.savings));
+    found
+}
+
+fn check(type_: &Type, min_ratio: f64, min_bytes: usize) -> Option<LargeVariant<'_>> {
+    let TypeKind::Enum(enum_) = &type_.kind else {
+        return None;
+    };
+
+    // Take the first one of the largest variants and the next one after it.
+    let mut variants = enum_.variants.iter().collect::<Vec<_>>();
+    variants.sort_by_key(|v| Reverse(v.size));
+    let (largest, next) = (variants.first()?, variants.get(1)?);
+
+    let is_large = largest.size >= next.size + min_bytes
+        && largest.size as f64 >= next.size as f64 * min_ratio;
+    if !is_large {
+        return None;
+    }
+
+    let fields = largest.items.iter().filter_map(|item| match item {
+        FieldOrPadding::Field(field) => Some(field),
+        FieldOrPadding::Padding(_) => None,
+    });
+
+    let upvars = fields
+        .clone()
+        .filter(|f| f.kind == FieldKind::Upvar)
+        .map(|f| f.size)
+        .sum::<usize>();
+
+    let field = fields
+        .filter(|f| f.kind != FieldKind::Upvar)
+        .reduce(|max, f| if f.size > max.size { f } else { max });
+
+    let boxed_size = (upvars + POINTER_SIZE).max(next.size);
+    let savings = largest.size.saturating_sub(boxed_size);
+    if savings == 0 {
+        return None;
+    }
+
+    Some(LargeVariant {
+        type_,
+        largest,
+        next,
+        field,
+        savings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_find() {
+        let input = [
+            "type: `E`: 264 bytes, alignment: 8 bytes",
+            "    discriminant: 8 bytes",
+            "    variant `Big`: 256 bytes",
+            "        field `.0`: 200 bytes",
+            "        field `.1`: 56 bytes",
+            "    variant `Small`: 16 bytes",
+            "        field `.0`: 16 bytes",
+            "type: `F`: 72 bytes, alignment: 8 bytes",
+            "    discriminant: 8 bytes",
+            "    variant `A`: 64 bytes",
+            "        field `.0`: 64 bytes",
+            "    variant `B`: 48 bytes",
+            "        field `.0`: 48 bytes",
+            "type: `{async fn body of f()}`: 264 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `Unresumed`: 64 bytes",
+            "        upvar `.x`: 64 bytes, offset: 0 bytes, alignment: 8 bytes",
+            "    variant `Suspend0`: 256 bytes",
+            "        upvar `.x`: 64 bytes, offset: 0 bytes, alignment: 8 bytes",
+            "        local `.__awaitee`: 192 bytes, alignment: 8 bytes",
+            "    end padding: 7 bytes",
+        ]
+        .join("\n");
+        let types = parser::parse(&input).unwrap();
+        let found = find(&types, 2., 64);
+
+        let summary = found
+            .iter()
+            .map(|v| {
+                (
+                    v.type_.name.as_str(),
+                    v.largest.name.as_str(),
+                    v.field.map(|f| f.name.as_str()),
+                    v.savings,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summary,
+            [
+                ("E", "Big", Some("0"), 240),
+                ("{async fn body of f()}", "Suspend0", Some("__awaitee"), 184),
+            ]
+        );
+        assert_eq!(found[0].ratio(), 16.);
+    }
+}
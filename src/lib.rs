@@ -1,8 +1,21 @@
 #![doc = include_str!("../README.md")]
 
+pub mod await_stats;
+pub mod blame;
+pub mod builder;
+pub mod checker;
+pub mod differ;
+pub mod duplicated_upvars;
 pub mod formatter;
+pub mod graph;
+pub mod held_locals;
+pub mod interleaving;
+pub mod large_variants;
 pub mod options;
 pub mod parser;
 pub mod reader;
 pub mod schema;
 pub mod transformer;
+pub mod validator;
+pub mod waste;
+pub mod wrapper;
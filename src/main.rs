@@ -1,15 +1,173 @@
+use std::collections::HashSet;
+
+use eyre::WrapErr;
 use structopt::StructOpt;
 
-use top_type_sizes::*;
+use top_type_sizes::{
+    options::{Command, Format, InputFormat, Options},
+    reader::Input,
+    schema::Type,
+    validator::Severity,
+    *,
+};
 
 fn main() -> eyre::Result<()> {
-    let options = options::Options::from_args();
-    let stdin = std::io::stdin().lock();
-    let data = reader::read(stdin)?;
-    let types = parser::parse(&data)?;
-    let types = transformer::transform(types, &options);
-    let output = formatter::format(types, &options);
+    if let Some((dir, args)) = wrapper::detect() {
+        let code = wrapper::run(&dir, &args)?;
+        std::process::exit(code);
+    }
+
+    let mut options = Options::from_args();
+    options.color = options.color.resolve();
+    check_views(&options)?;
+
+    let output = match &options.command {
+        Some(Command::Diff { old, new }) => {
+            if !matches!(
+                options.format,
+                Format::Text | Format::Json | Format::Ndjson | Format::Markdown
+            ) {
+                eyre::bail!("only text, json, ndjson and markdown formats are supported by diff");
+            }
+
+            let old = report(reader::read_path(old, &options)?, &options)?;
+            let new = report(reader::read_path(new, &options)?, &options)?;
+            let diffs = differ::diff(&old, &new, &options);
+            formatter::format_diff(diffs, &options)
+        }
+        Some(Command::Check {
+            budget,
+            deny_unmatched,
+        }) => {
+            let budget = std::fs::read_to_string(budget)
+                .wrap_err_with(|| format!("cannot read {}", budget.display()))?;
+            let rules = checker::parse_rules(&budget).wrap_err("invalid budget file")?;
+            let types = transformer::transform(load(&options)?, &options);
+            let violations = checker::check(&types, &rules, *deny_unmatched);
+
+            if !violations.is_empty() {
+                for violation in &violations {
+                    println!("{violation}");
+                }
+
+                println!("\n{}", checker::summary(&violations, &rules));
+                std::process::exit(1);
+            }
+
+            format!("all {} rules passed", rules.len())
+        }
+        Some(Command::Build {
+            output,
+            toolchain,
+            cargo_args,
+        }) => {
+            let raw = builder::build(toolchain, cargo_args, output)?;
+            // The captured output is always the compiler's text.
+            let text_options = Options {
+                input_format: InputFormat::Text,
+                ..options.clone()
+            };
+            let types = report(reader::read_types(raw.as_bytes(), &text_options)?, &options)?;
+            let types = transformer::transform(types, &options);
+            formatter::format(types, &options)
+        }
+        None => {
+            let types = transformer::transform(load(&options)?, &options);
+            formatter::format(types, &options)
+        }
+    };
 
     println!("{output}");
     Ok(())
 }
+
+/// Fails if a view is requested in a format other than text, which would
+/// silently ignore it.
+fn check_views(options: &Options) -> eyre::Result<()> {
+    if options.format == Format::Text {
+        return Ok(());
+    }
+
+    let views = [
+        ("--large-variants", options.large_variants),
+        ("--waste", options.waste),
+        ("--held-locals", options.held_locals),
+        ("--duplicated-upvars", options.duplicated_upvars),
+        ("--await-stats", options.await_stats),
+    ];
+
+    if let Some((flag, _)) = views.iter().find(|(_, enabled)| *enabled) {
+        eyre::bail!("{flag} is supported only by the text format");
+    }
+
+    Ok(())
+}
+
+fn load(options: &Options) -> eyre::Result<Vec<Type>> {
+    let input = match &options.input {
+        Some(path) => reader::read_path(path, options)?,
+        None => reader::read_types(std::io::stdin().lock(), options)?,
+    };
+
+    report(input, options)
+}
+
+/// Reports skipped regions, quarantined types and validation warnings to
+/// stderr. Fails if validation is enabled and some types are inconsistent.
+fn report(input: Input, options: &Options) -> eyre::Result<Vec<Type>> {
+    for skipped in &input.skipped {
+        let source = skipped.source.as_deref().unwrap_or("the input");
+        eprintln!(
+            "skipped {} lines of {source} starting at line {}:\n{}\n",
+            skipped.line_count, skipped.line_no, skipped.context
+        );
+    }
+
+    if !input.skipped.is_empty() {
+        eprintln!("unparsable blocks skipped: {}", input.skipped.len());
+    }
+
+    for quarantined in &input.quarantined {
+        let type_ = &quarantined.type_;
+        let crate_name = type_.crate_name.as_deref().unwrap_or("unknown crate");
+        eprintln!(
+            "quarantined {} ({crate_name}): {} bytes, but {}",
+            type_.name, type_.size, quarantined.reason
+        );
+    }
+
+    if !input.quarantined.is_empty() {
+        eprintln!(
+            concat!(
+                "types quarantined as broken by interleaved output: {}\n",
+                "Probably, the build was parallel, pass -j1 to cargo or use the `build` subcommand."
+            ),
+            input.quarantined.len()
+        );
+    }
+
+    for finding in &input.findings {
+        let crate_name = finding.crate_name.as_deref().unwrap_or("unknown crate");
+        eprintln!(
+            "{}: {} ({crate_name}): {}",
+            finding.issue.severity(),
+            finding.type_name,
+            finding.issue
+        );
+    }
+
+    let inconsistent = input.quarantined.len()
+        + input
+            .findings
+            .iter()
+            .filter(|f| f.issue.severity() == Severity::Error)
+            .map(|f| &f.type_name)
+            .collect::<HashSet<_>>()
+            .len();
+
+    if options.validate && inconsistent > 0 {
+        eyre::bail!("validation failed: {inconsistent} types have inconsistent layouts");
+    }
+
+    Ok(input.types)
+}
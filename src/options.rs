@@ -1,5 +1,7 @@
+use std::{io::IsTerminal, path::PathBuf, str::FromStr};
+
 use regex::Regex;
-use structopt::StructOpt;
+use structopt::{clap::AppSettings, StructOpt};
 
 #[derive(Debug, Clone, StructOpt)]
 pub struct Options {
@@ -35,31 +37,543 @@ pub struct Options {
     /// Patterns are regex (in the regex crate's syntax).
     /// Can be provided multiple times.
     /// {n}{n}{n}
-    #[structopt(short = "f", long)]
+    #[structopt(short = "f", long, number_of_values = 1)]
     pub filter: Vec<Regex>,
     /// Excludes types that match these patterns.
     ///
     /// Patterns are regex (in the regex crate's syntax).
     /// Can be provided multiple times.
     /// {n}{n}{n}
-    #[structopt(short = "e", long)]
+    #[structopt(short = "e", long, number_of_values = 1)]
     pub exclude: Vec<Regex>,
     /// Shows only types that match these patterns and their children.
     ///
     /// It uses two mechanisms to expand types: {n}
     /// - by field's type name (requires at least nightly 24-03-22) {n}
-    /// - by field's size if the `--expand_by_size` option is enabled
+    /// - by field's size and alignment if the `--expand_by_size` option is enabled
     ///
     /// Note: currently field's type names are provided only for `await`.
     ///
     /// Patterns are regex (in the regex crate's syntax).
     /// Can be provided multiple times.
     /// {n}{n}{n}
-    #[structopt(short = "p", long)]
+    #[structopt(short = "p", long, number_of_values = 1)]
     pub expand: Vec<Regex>,
-    /// Modify the -p/--expand option to expand also by field's size.
+    /// Shows only types laid out in crates that match these patterns.
+    ///
+    /// Crates are detected by cargo's `Compiling` lines, so stderr must be
+    /// captured too (e.g. `2>&1`). The `build` subcommand and the wrapper
+    /// mode detect crates automatically and precisely.
+    ///
+    /// Patterns are regex (in the regex crate's syntax).
+    /// Can be provided multiple times.
+    /// {n}{n}{n}
+    #[structopt(long = "crate", number_of_values = 1)]
+    pub crates: Vec<Regex>,
+    /// Excludes types laid out in crates that match these patterns.
+    ///
+    /// Patterns are regex (in the regex crate's syntax).
+    /// Can be provided multiple times.
+    /// {n}{n}{n}
+    #[structopt(long, number_of_values = 1)]
+    pub exclude_crate: Vec<Regex>,
+    /// Shows chains of fields containing types that match these patterns.
+    ///
+    /// Chains start from types not contained in other types, e.g. root
+    /// futures. Containing types are detected the same way as for the
+    /// -p/--expand option. Other formats show only types that match these
+    /// patterns and types containing them. The -l/--limit option is applied
+    /// to matched types.
+    ///
+    /// Patterns are regex (in the regex crate's syntax).
+    /// Can be provided multiple times.
+    /// {n}{n}{n}
+    #[structopt(
+        long,
+        visible_alias = "who-contains",
+        number_of_values = 1,
+        conflicts_with_all = &["expand", "tree"]
+    )]
+    pub parents: Vec<Regex>,
+    /// Shows why types that match these patterns are so big.
+    ///
+    /// Breaks down the largest variant of every matched type into fields,
+    /// fields with known types (e.g. awaitees) are broken down recursively.
+    /// Parts are ranked by size, with percentages of the matched type.
+    /// Other formats show only types that match these patterns and their
+    /// children. The -l/--limit option is applied to matched types.
+    ///
+    /// Patterns are regex (in the regex crate's syntax).
+    /// Can be provided multiple times.
+    /// {n}{n}{n}
+    #[structopt(
+        long,
+        number_of_values = 1,
+        conflicts_with_all = &["expand", "tree", "parents"]
+    )]
+    pub blame: Vec<Regex>,
+    /// Shows enums whose largest variant is much bigger than the next one.
+    ///
+    /// Covers both regular enums and coroutines, e.g. `{async fn body}`.
+    /// Every enum is shown with the estimated bytes saved if the payload of
+    /// its largest variant is boxed (upvars of coroutines aren't boxed) and
+    /// the field dominating that variant. Enums are ranked by savings. The
+    /// -l/--limit option is applied to found enums.
+    ///
+    /// Only for the text format.
+    /// {n}{n}{n}
+    #[structopt(long, conflicts_with_all = &["expand", "tree", "parents", "blame"])]
+    pub large_variants: bool,
+    /// The minimal ratio of sizes of the largest and next variants for the
+    /// --large-variants option, 2 by default.
+    #[structopt(long, requires = "large-variants")]
+    pub min_variant_ratio: Option<f64>,
+    /// The minimal difference of sizes of the largest and next variants for
+    /// the --large-variants option, 64 by default.
+    #[structopt(long, requires = "large-variants")]
+    pub min_variant_bytes: Option<usize>,
+    /// Shows paddings of types and their total over all shown types.
+    ///
+    /// Paddings between fields (of the largest variant for enums) and end
+    /// paddings are wasted in every value of a type. Paddings of every
+    /// variant are shown too. Types are ranked by wasted bytes or by their
+    /// share of the type's size (see --waste-by). The -l/--limit option is
+    /// applied to found types, the total counts all of them. The -h option
+    /// hides only small types, not paddings.
+    ///
+    /// Only for the text format.
+    /// {n}{n}{n}
+    #[structopt(
+        long,
+        conflicts_with_all = &["expand", "tree", "parents", "blame", "large-variants", "sort-fields"]
+    )]
+    pub waste: bool,
+    /// Ranks types of the --waste option by wasted bytes or percent.
+    #[structopt(
+        long,
+        default_value = "bytes",
+        possible_values = &["bytes", "percent"]
+    )]
+    pub waste_by: WasteOrder,
+    /// Shows which suspend states of futures hold which locals.
+    ///
+    /// Builds a matrix of locals and suspend states for every coroutine,
+    /// e.g. `{async fn body}`. Locals are ranked by bytes held over all
+    /// states, i.e. size multiplied by the number of states holding them.
+    /// Coroutines are ranked by their top locals. The -l/--limit option is
+    /// applied to found coroutines.
+    ///
+    /// Only for the text format.
+    /// {n}{n}{n}
+    #[structopt(
+        long,
+        conflicts_with_all = &["expand", "tree", "parents", "blame", "large-variants", "waste"]
+    )]
+    pub held_locals: bool,
+    /// Shows upvars of futures also stored as locals with the same name.
+    ///
+    /// An upvar is duplicated if a suspend state of a coroutine, e.g.
+    /// `{async fn body}`, also holds a local with the same name and size,
+    /// usually an argument moved into a local before an await. Coroutines are
+    /// ranked by duplicated bytes. The -l/--limit option is applied to found
+    /// coroutines.
+    ///
+    /// Only for the text format.
+    /// {n}{n}{n}
+    #[structopt(
+        long,
+        conflicts_with_all = &[
+            "expand", "tree", "parents", "blame", "large-variants", "waste", "held-locals"
+        ]
+    )]
+    pub duplicated_upvars: bool,
+    /// Shows statistics of awaits of futures as a table.
+    ///
+    /// For every coroutine, e.g. `{async fn body}`, shows the maximum nesting
+    /// of awaitees, the number of await points (suspend states), the number
+    /// of distinct awaited futures and the share of the largest awaitee in
+    /// the coroutine's size. Awaitees are followed by their types, only among
+    /// shown types. The -l/--limit option is applied to rows, which are
+    /// sorted by the --await-stats-by option.
+    ///
+    /// Only for the text format.
+    /// {n}{n}{n}
+    #[structopt(
+        long,
+        conflicts_with_all = &[
+            "expand",
+            "tree",
+            "parents",
+            "blame",
+            "large-variants",
+            "waste",
+            "held-locals",
+            "duplicated-upvars"
+        ]
+    )]
+    pub await_stats: bool,
+    /// Sorts rows of the --await-stats option by this column.
+    #[structopt(
+        long,
+        default_value = "size",
+        possible_values = &["size", "depth", "suspends", "awaitees", "share"]
+    )]
+    pub await_stats_by: AwaitStatsOrder,
+    /// Modify the -p/--expand and --parents options to use field's size too.
     #[structopt(long)]
     pub expand_by_size: bool,
+    /// Shows types as trees, nesting every type under fields containing it.
+    ///
+    /// Children are detected the same way as for the -p/--expand option,
+    /// types matching -p/--expand are roots. Repeated subtrees and cycles
+    /// are expanded only once. The -l/--limit option is applied to roots.
+    ///
+    /// Only for the text format.
+    /// {n}{n}{n}
+    #[structopt(long)]
+    pub tree: bool,
+    /// Limits depth of the tree view, roots have depth 0.
+    #[structopt(long, requires = "tree")]
+    pub tree_depth: Option<usize>,
+    /// Output format: text, json, ndjson, dot, folded, html, markdown, csv or
+    /// tsv.
+    ///
+    /// The json format prints one document with the schema version, the
+    /// ndjson format prints one type per line, every line also contains the
+    /// schema version. The dot format prints a Graphviz graph of types
+    /// containing other types, edges matched by size are dashed. The folded
+    /// format prints folded stacks for flamegraphs, types are broken down the
+    /// same way as for the --blame option. The html format prints a
+    /// self-contained page with collapsible and searchable types. The
+    /// markdown format prints tables for PR comments, truncated to fit
+    /// GitHub's comment size limit. The csv and tsv formats print one row per
+    /// type, variant or field (see --granularity).
+    ///
+    /// The diff subcommand supports only text, json, ndjson and markdown.
+    /// {n}{n}{n}
+    #[structopt(
+        long,
+        default_value = "text",
+        possible_values = &[
+            "text", "json", "ndjson", "dot", "folded", "html", "markdown", "csv", "tsv"
+        ]
+    )]
+    pub format: Format,
+    /// Colors the text format: auto, always or never.
+    ///
+    /// The auto mode enables colors if stdout is a terminal and the NO_COLOR
+    /// environment variable isn't set.
+    /// {n}{n}{n}
+    #[structopt(
+        long,
+        default_value = "auto",
+        possible_values = &["auto", "always", "never"]
+    )]
+    pub color: ColorChoice,
+    /// Rows of the csv and tsv formats: type, variant or field.
+    ///
+    /// Types are shown with their largest variants, structs have one row with
+    /// an empty variant for the variant granularity. The field granularity
+    /// also has rows for paddings and discriminants.
+    /// {n}{n}{n}
+    #[structopt(
+        long,
+        default_value = "field",
+        possible_values = &["type", "variant", "field"]
+    )]
+    pub granularity: Granularity,
+    /// Input format: auto, text or json.
+    ///
+    /// The json format accepts output of both --format json and --format
+    /// ndjson. The auto format detects JSON by the first character.
+    /// {n}{n}{n}
+    #[structopt(
+        long,
+        default_value = "auto",
+        possible_values = &["auto", "text", "json"]
+    )]
+    pub input_format: InputFormat,
+    /// Skips unparsable blocks of the input instead of failing.
+    ///
+    /// Every skipped region is reported with context, the parser continues
+    /// from the next `type:` line. Types with inconsistent layouts (e.g.
+    /// because of interleaved output) are excluded as quarantined.
+    /// {n}{n}{n}
+    #[structopt(long)]
+    pub lenient: bool,
+    /// Verifies layouts of parsed types.
+    ///
+    /// Reports inconsistent layouts as errors and suspicious layouts as
+    /// warnings. Fails if any layout is inconsistent, including types
+    /// quarantined by --lenient. Not supported for JSON input.
+    /// {n}{n}{n}
+    #[structopt(long)]
+    pub validate: bool,
+    /// Reads the input from this file instead of stdin.
+    ///
+    /// If it's a directory, all `*.txt` and `*.json` files in it are read,
+    /// e.g. collected in the wrapper mode.
+    /// {n}{n}{n}
+    #[structopt(short = "i", long, parse(from_os_str))]
+    pub input: Option<PathBuf>,
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub enum Command {
+    /// Compares two captures and shows which types have changed.
+    ///
+    /// Types are matched by name, variants and fields are matched by name
+    /// inside matched types. Unchanged types are hidden.
+    ///
+    /// The -f, -e, -w and -h options are applied to both captures, a type is
+    /// shown if it's visible in any of them. The -l and -r options are
+    /// applied to the list of changed types.
+    /// {n}{n}{n}
+    Diff {
+        /// A capture before changes.
+        #[structopt(parse(from_os_str))]
+        old: PathBuf,
+        /// A capture after changes.
+        #[structopt(parse(from_os_str))]
+        new: PathBuf,
+    },
+    /// Checks sizes of types against a budget file.
+    ///
+    /// Every non-empty line of the budget file, except comments starting
+    /// with `#`, is a rule `<pattern> <= <max size in bytes>`. Patterns are
+    /// regex (in the regex crate's syntax), a pattern wrapped into backticks
+    /// matches the exact type name.
+    ///
+    /// Rules are applied after all other options. Exits with a non-zero code
+    /// if any rule is violated.
+    /// {n}{n}{n}
+    Check {
+        /// A budget file.
+        #[structopt(parse(from_os_str))]
+        budget: PathBuf,
+        /// Reports rules that match no types as violations.
+        #[structopt(long)]
+        deny_unmatched: bool,
+    },
+    /// Builds the project with `-Zprint-type-sizes` and shows its types.
+    ///
+    /// Runs `cargo +nightly build -j1` in a fresh temporary target directory
+    /// with the given arguments, e.g. `build --example chat`.
+    /// Options of this subcommand must precede them, `--` can separate them.
+    ///
+    /// The compiler's output is saved to reuse it without rebuilding.
+    /// {n}{n}{n}
+    #[structopt(settings = &[AppSettings::TrailingVarArg, AppSettings::AllowLeadingHyphen])]
+    Build {
+        /// Saves the compiler's output to this file.
+        #[structopt(
+            short = "o",
+            long,
+            default_value = "type-sizes.txt",
+            parse(from_os_str)
+        )]
+        output: PathBuf,
+        /// A toolchain supporting `-Zprint-type-sizes`.
+        #[structopt(long, default_value = "nightly")]
+        toolchain: String,
+        /// Arguments passed to `cargo build`.
+        #[structopt(allow_hyphen_values = true)]
+        cargo_args: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    Ndjson,
+    Dot,
+    Folded,
+    Html,
+    Markdown,
+    Csv,
+    Tsv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "ndjson" => Ok(Self::Ndjson),
+            "dot" => Ok(Self::Dot),
+            "folded" => Ok(Self::Folded),
+            "html" => Ok(Self::Html),
+            "markdown" => Ok(Self::Markdown),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            _ => Err(format!("unknown format `{s}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!("unknown color mode `{s}`")),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Resolves the auto mode for stdout, see <https://no-color.org>.
+    pub fn resolve(self) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        if !no_color && std::io::stdout().is_terminal() {
+            Self::Always
+        } else {
+            Self::Never
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Type,
+    Variant,
+    Field,
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "type" => Ok(Self::Type),
+            "variant" => Ok(Self::Variant),
+            "field" => Ok(Self::Field),
+            _ => Err(format!("unknown granularity `{s}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasteOrder {
+    Bytes,
+    Percent,
+}
+
+impl FromStr for WasteOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bytes" => Ok(Self::Bytes),
+            "percent" => Ok(Self::Percent),
+            _ => Err(format!("unknown order `{s}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AwaitStatsOrder {
+    Size,
+    Depth,
+    Suspends,
+    Awaitees,
+    Share,
+}
+
+impl FromStr for AwaitStatsOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "size" => Ok(Self::Size),
+            "depth" => Ok(Self::Depth),
+            "suspends" => Ok(Self::Suspends),
+            "awaitees" => Ok(Self::Awaitees),
+            "share" => Ok(Self::Share),
+            _ => Err(format!("unknown column `{s}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    Auto,
+    Text,
+    Json,
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown input format `{s}`")),
+        }
+    }
+}
+
+impl Options {
+    /// Checks if types are printed as trees (see `--tree`).
+    pub fn is_tree_view(&self) -> bool {
+        self.tree && self.format == Format::Text
+    }
+
+    /// Checks if chains of containing types are printed (see `--parents`).
+    pub fn is_parents_view(&self) -> bool {
+        !self.parents.is_empty() && self.format == Format::Text
+    }
+
+    /// Checks if breakdowns of sizes are printed (see `--blame`).
+    pub fn is_blame_view(&self) -> bool {
+        !self.blame.is_empty() && self.format == Format::Text
+    }
+
+    /// Checks if enums with large variants are printed (see `--large-variants`).
+    pub fn is_large_variants_view(&self) -> bool {
+        self.large_variants && self.format == Format::Text
+    }
+
+    /// Checks if paddings of types are printed (see `--waste`).
+    pub fn is_waste_view(&self) -> bool {
+        self.waste && self.format == Format::Text
+    }
+
+    /// Checks if matrices of held locals are printed (see `--held-locals`).
+    pub fn is_held_locals_view(&self) -> bool {
+        self.held_locals && self.format == Format::Text
+    }
+
+    /// Checks if duplicated upvars are printed (see `--duplicated-upvars`).
+    pub fn is_duplicated_upvars_view(&self) -> bool {
+        self.duplicated_upvars && self.format == Format::Text
+    }
+
+    /// Checks if statistics of awaits are printed (see `--await-stats`).
+    pub fn is_await_stats_view(&self) -> bool {
+        self.await_stats && self.format == Format::Text
+    }
 }
 
 impl Default for Options {
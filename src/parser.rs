@@ -168,6 +168,7 @@ fn type_(input: &str) -> IResult<&str, Type> {
         align,
         kind,
         end_padding,
+        crate_name: None,
     };
 
     Ok((input, type_))
@@ -179,36 +180,152 @@ fn types(input: &str) -> IResult<&str, Vec<Type>> {
 
 /// Parses refined (without the prefix) input.
 pub fn parse(input: &str) -> eyre::Result<Vec<Type>> {
-    // TODO: check recovery and failures.
+    parse_numbered(input, None)
+}
+
+/// Same as [`parse`], but reports line numbers of the original input, where
+/// `line_nos` contains the original line number of every refined line.
+pub(crate) fn parse_numbered(input: &str, line_nos: Option<&[usize]>) -> eyre::Result<Vec<Type>> {
     let (rest, types) = types(input)
         .finish()
         .map_err(|err| eyre::eyre!(err.to_string()))?;
 
     if !rest.is_empty() {
         let pos = input.len() - rest.len();
-        let (error_line_no, context) = format_context(input, pos);
-        return Err(eyre::eyre!(
+        let (error_line_no, context) = format_context(input, pos, line_nos);
+
+        let hint = if is_known_line(rest.trim_start_matches('\n')) {
+            concat!(
+                "The line is valid, but doesn't fit the current block. ",
+                "Probably, the output is interleaved by a parallel build.\n",
+                "Run `cargo clean && RUSTFLAGS=-Zprint-type-sizes cargo +nightly build -j1 > type-sizes.txt` ",
+                "or use the `build` subcommand.\n",
+                "Alternatively, pass --lenient to skip broken blocks."
+            )
+        } else {
             concat!(
-                "cannot parse at line {}:\n{}\n\n",
-                "Make sure the build directory is clean and that the -j1 flag is passed to the compiler.\n",
-                "Run `cargo clean && RUSTFLAGS=-Zprint-type-sizes cargo +nightly build -j1 > type-sizes.txt`.\n",
-                "If the issue persists, please file an issue on GitHub."
-            ),
+                "The line has an unknown format. ",
+                "Probably, the compiler's output format has changed.\n",
+                "Please file an issue on GitHub, meanwhile pass --lenient to skip such blocks."
+            )
+        };
+
+        return Err(eyre::eyre!(
+            "cannot parse at line {}:\n{}\n\n{}",
             error_line_no,
-            context
+            context,
+            hint
         ));
     }
 
     Ok(types)
 }
 
-fn format_context(input: &str, pos: usize) -> (usize, String) {
+/// A region of the input skipped by the lenient parser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Skipped {
+    /// A file or a crate containing the region, if known.
+    pub source: Option<String>,
+    /// A line number where the region starts, in refined input unless
+    /// original line numbers are provided (e.g. by `reader`).
+    pub line_no: usize,
+    /// A number of skipped lines.
+    pub line_count: usize,
+    /// Lines around the beginning of the region.
+    pub context: String,
+    /// An index of the type parsed right before the region, if any. Such type
+    /// may be truncated.
+    pub preceded_by: Option<usize>,
+}
+
+/// Parses refined (without the prefix) input, but instead of failing on the
+/// first unparsable block, skips lines until the next `type: ` line.
+///
+/// Returns all parsed types and skipped regions.
+pub fn parse_lenient(input: &str) -> (Vec<Type>, Vec<Skipped>) {
+    parse_lenient_numbered(input, None)
+}
+
+/// Same as [`parse_lenient`], but reports line numbers of the original input,
+/// see [`parse_numbered`].
+pub(crate) fn parse_lenient_numbered(
+    input: &str,
+    line_nos: Option<&[usize]>,
+) -> (Vec<Type>, Vec<Skipped>) {
+    let mut all_types = Vec::new();
+    let mut skipped = Vec::new();
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let parsed_before = all_types.len();
+        let rest = match types(&input[pos..]) {
+            Ok((rest, types)) => {
+                all_types.extend(types);
+                rest
+            }
+            Err(_) => &input[pos..],
+        };
+
+        if rest.trim().is_empty() {
+            break;
+        }
+
+        pos = input.len() - rest.len();
+        let start = pos + usize::from(rest.starts_with('\n'));
+
+        // Resynchronize at the next type.
+        let end = input[start..]
+            .find("\ntype: ")
+            .map_or(input.len(), |offset| start + offset + 1);
+
+        let (line_no, context) = format_context(input, pos, line_nos);
+        skipped.push(Skipped {
+            source: None,
+            line_no,
+            line_count: input[start..end].trim_end().lines().count(),
+            context,
+            preceded_by: (all_types.len() > parsed_before).then(|| all_types.len() - 1),
+        });
+
+        pos = end;
+    }
+
+    (all_types, skipped)
+}
+
+// Checks if the line is produced by the compiler, even if it's misplaced.
+fn is_known_line(line: &str) -> bool {
+    let line = line.trim_start_matches(' ');
+
+    let known = alt((
+        map(field, |_| ()),
+        map(padding, |_| ()),
+        map(tag("variant "), |_| ()),
+        map(tag("discriminant: "), |_| ()),
+        map(tag("end padding: "), |_| ()),
+        map(tag("type: "), |_| ()),
+    ))(line);
+
+    known.is_ok()
+}
+
+fn format_context(input: &str, pos: usize, line_nos: Option<&[usize]>) -> (usize, String) {
     const CONTEXT_LINES: usize = 10;
 
+    // Maps a zero-based refined line to a line number shown to the user.
+    let original_line_no = |line_no: usize| {
+        line_nos
+            .and_then(|nos| nos.get(line_no).copied())
+            .unwrap_or(line_no + 1)
+    };
+
     let error_line_no = input[..pos + 1].matches('\n').count();
     let start_no = error_line_no.saturating_sub(CONTEXT_LINES);
     let end_no = error_line_no + CONTEXT_LINES;
-    let line_no_width = (end_no + 1).ilog10() as usize + 1;
+    let max_line_no = line_nos
+        .and_then(|nos| nos[..nos.len().min(end_no + 1)].last().copied())
+        .map_or(end_no + 1, |no| no.max(end_no + 1));
+    let line_no_width = max_line_no.ilog10() as usize + 1;
 
     let context = input
         .lines()
@@ -223,7 +340,7 @@ fn format_context(input: &str, pos: usize) -> (usize, String) {
                 } else {
                     "    "
                 },
-                line_no = line_no + 1,
+                line_no = original_line_no(line_no),
                 line = line,
                 line_no_width = line_no_width
             )
@@ -231,7 +348,7 @@ fn format_context(input: &str, pos: usize) -> (usize, String) {
         .collect::<Vec<_>>()
         .join("\n");
 
-    (error_line_no + 1, context)
+    (original_line_no(error_line_no), context)
 }
 
 #[cfg(test)]
@@ -245,4 +362,36 @@ mod tests {
         assert_eq!(indent(2)("\n        "), Ok(("", ())));
         assert_ne!(indent(2)("\n    "), Ok(("", ())));
     }
+
+    #[test]
+    fn test_parse_lenient() {
+        let input = [
+            "type: `A`: 8 bytes, alignment: 8 bytes",
+            "    field `.a`: 8 bytes",
+            "    garbage",
+            "        field `.b`: 4 bytes",
+            "type: `B`: 4 bytes, alignment: 4 bytes",
+            "    field `.b`: 4 bytes",
+            "garbage",
+            "type: `C`: 2 bytes, alignment: 2 bytes",
+            "    field `.c`: 2 bytes",
+        ]
+        .join("\n");
+
+        assert!(parse(&input).is_err());
+
+        let (types, skipped) = parse_lenient(&input);
+        let names = types.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["A", "B", "C"]);
+
+        assert_eq!(skipped.len(), 2);
+        assert_eq!((skipped[0].line_no, skipped[0].line_count), (3, 2));
+        assert_eq!((skipped[1].line_no, skipped[1].line_count), (7, 1));
+        let marked = skipped[1].context.lines().find(|l| l.starts_with(">>>"));
+        assert!(marked.unwrap().ends_with("7 │ garbage"));
+
+        let (types, skipped) = parse_lenient("garbage\n    more garbage");
+        assert!(types.is_empty());
+        assert_eq!((skipped[0].line_no, skipped[0].line_count), (1, 2));
+    }
 }
@@ -1,24 +1,300 @@
-use std::io::BufRead;
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use eyre::WrapErr;
+use serde::Deserialize;
+
+use crate::{
+    interleaving::{self, Quarantined},
+    options::{InputFormat, Options},
+    parser::{self, Skipped},
+    schema,
+    validator::{self, Finding},
+};
+
+/// Types read from the input.
+#[derive(Debug, Default)]
+pub struct Input {
+    pub types: Vec<schema::Type>,
+    /// Regions skipped by the lenient parser (see `--lenient`).
+    pub skipped: Vec<Skipped>,
+    /// Types broken by interleaved output, not included in `types`. Only
+    /// detected by the lenient parser (see `--lenient`).
+    pub quarantined: Vec<Quarantined>,
+    /// Warnings for included types (see `--validate`).
+    pub findings: Vec<Finding>,
+}
+
+impl Input {
+    fn extend(&mut self, other: Input) {
+        self.types.extend(other.types);
+        self.skipped.extend(other.skipped);
+        self.quarantined.extend(other.quarantined);
+        self.findings.extend(other.findings);
+    }
+}
+
+/// The compiler's output for one crate.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    /// Detected by headers written by the `build` subcommand and the wrapper
+    /// mode (see [`crate_header`]) or by cargo's `Compiling` lines.
+    pub crate_name: Option<String>,
+    /// Lines without the `print-type-size ` prefix.
+    pub data: String,
+    /// Line numbers in the input of every line in `data`.
+    pub line_nos: Vec<usize>,
+}
 
 /// Strips the `print-type-size ` prefix and ignores unprefixed lines.
-pub fn read(mut rd: impl BufRead) -> eyre::Result<String> {
+pub fn read(rd: impl BufRead) -> eyre::Result<String> {
+    let data = read_chunks(rd)?
+        .into_iter()
+        .map(|chunk| chunk.data)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(data)
+}
+
+/// Strips the `print-type-size ` prefix and splits the output by crates.
+/// Other unprefixed lines are ignored.
+///
+/// Crate headers are precise, so once one is found, cargo's `Compiling`
+/// lines are ignored.
+pub fn read_chunks(mut rd: impl BufRead) -> eyre::Result<Vec<Chunk>> {
     let mut line = String::with_capacity(4096);
-    let mut result = String::new();
+    let mut line_no = 0;
+    let mut has_headers = false;
+    let mut chunks = vec![Chunk::default()];
 
     while rd.read_line(&mut line)? > 0 {
-        let Some(refined_line) = line.strip_prefix("print-type-size ") else {
-            line.clear();
-            continue;
-        };
+        line_no += 1;
+
+        if let Some(refined_line) = line.strip_prefix("print-type-size ") {
+            let chunk = chunks.last_mut().expect("at least one chunk");
+            chunk.data.push_str(refined_line);
+            chunk.line_nos.push(line_no);
+        } else if let Some(crate_name) = header_crate(&line) {
+            has_headers = true;
+            chunks.push(Chunk {
+                crate_name: Some(crate_name.into()),
+                ..Chunk::default()
+            });
+        } else if let Some(crate_name) = compiling_crate(&line).filter(|_| !has_headers) {
+            chunks.push(Chunk {
+                crate_name: Some(crate_name.into()),
+                ..Chunk::default()
+            });
+        }
 
-        result.push_str(refined_line);
         line.clear();
     }
 
-    // Handle a trailing newline.
-    if result.ends_with('\n') {
-        result.pop();
+    for chunk in &mut chunks {
+        // Handle a trailing newline.
+        if chunk.data.ends_with('\n') {
+            chunk.data.pop();
+        }
+    }
+
+    chunks.retain(|chunk| !chunk.data.is_empty());
+    Ok(chunks)
+}
+
+const CRATE_HEADER: &str = "top-type-sizes: crate ";
+
+/// Returns a line preceding the compiler's output for one crate.
+///
+/// Unlike cargo's `Compiling` lines, which are printed once per package, it
+/// precedes every compilation unit, e.g. a build script and a library of the
+/// same package can be compiled at different times.
+pub fn crate_header(name: &str, version: &str) -> String {
+    format!("{CRATE_HEADER}{name} v{version}\n")
+}
+
+// Example: "top-type-sizes: crate tokio v1.36.0"
+fn header_crate(line: &str) -> Option<&str> {
+    let mut parts = line.strip_prefix(CRATE_HEADER)?.trim_end().split(' ');
+    let crate_name = parts.next()?;
+    let version = parts.next()?;
+    version.starts_with('v').then_some(crate_name)
+}
+
+// Example: "   Compiling tokio v1.36.0"
+fn compiling_crate(line: &str) -> Option<&str> {
+    let mut parts = line.trim_start().strip_prefix("Compiling ")?.split(' ');
+    let crate_name = parts.next()?;
+    let version = parts.next()?;
+    version.starts_with('v').then_some(crate_name)
+}
+
+/// Reads types from the compiler's output or from exported JSON.
+pub fn read_types(mut rd: impl BufRead, options: &Options) -> eyre::Result<Input> {
+    let is_json = match options.input_format {
+        InputFormat::Auto => rd
+            .fill_buf()?
+            .iter()
+            .find(|b| !b.is_ascii_whitespace())
+            .is_some_and(|b| *b == b'{'),
+        InputFormat::Text => false,
+        InputFormat::Json => true,
+    };
+
+    if is_json {
+        // Exported layouts can be partial (e.g. after `-h` or `-s`).
+        if options.validate {
+            eyre::bail!("--validate isn't supported for JSON input");
+        }
+
+        let mut data = String::new();
+        rd.read_to_string(&mut data)?;
+
+        Ok(Input {
+            types: read_json(&data)?,
+            ..Input::default()
+        })
+    } else {
+        read_text(rd, options)
+    }
+}
+
+/// Reads types from a file or from all `*.txt` and `*.json` files in a
+/// directory (e.g. collected in the wrapper mode).
+pub fn read_path(path: &Path, options: &Options) -> eyre::Result<Input> {
+    if !path.is_dir() {
+        let file = File::open(path).wrap_err_with(|| format!("cannot open {}", path.display()))?;
+        let mut input = read_types(BufReader::new(file), options)
+            .wrap_err_with(|| format!("cannot read {}", path.display()))?;
+
+        for skipped in &mut input.skipped {
+            skipped.source = Some(match &skipped.source {
+                Some(source) => format!("{}, {source}", path.display()),
+                None => path.display().to_string(),
+            });
+        }
+
+        return Ok(input);
+    }
+
+    let mut paths = fs::read_dir(path)
+        .wrap_err_with(|| format!("cannot read {}", path.display()))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    paths.retain(|p| p.is_file() && p.extension().is_some_and(|e| e == "txt" || e == "json"));
+    paths.sort();
+
+    let mut input = Input::default();
+    for path in paths {
+        input.extend(read_path(&path, options)?);
+    }
+
+    Ok(input)
+}
+
+fn read_text(rd: impl BufRead, options: &Options) -> eyre::Result<Input> {
+    let mut input = Input::default();
+
+    for chunk in read_chunks(rd)? {
+        let (mut types, mut skipped) = if options.lenient {
+            parser::parse_lenient_numbered(&chunk.data, Some(&chunk.line_nos))
+        } else {
+            let result = parser::parse_numbered(&chunk.data, Some(&chunk.line_nos));
+            let types = match &chunk.crate_name {
+                Some(crate_name) => result
+                    .wrap_err_with(|| format!("cannot parse output of the `{crate_name}` crate"))?,
+                None => result?,
+            };
+
+            (types, Vec::new())
+        };
+
+        // A successfully parsed capture has no evidence of interleaving, so
+        // inconsistent types are only reported by `--validate`.
+        let mut quarantined = if options.lenient {
+            interleaving::quarantine(&mut types, &skipped)
+        } else {
+            Vec::new()
+        };
+
+        for type_ in types
+            .iter_mut()
+            .chain(quarantined.iter_mut().map(|q| &mut q.type_))
+        {
+            type_.crate_name.clone_from(&chunk.crate_name);
+        }
+
+        for skipped in &mut skipped {
+            skipped.source = chunk
+                .crate_name
+                .as_ref()
+                .map(|name| format!("the `{name}` crate"));
+        }
+
+        let findings = if options.validate {
+            types.iter().flat_map(find_issues).collect()
+        } else {
+            Vec::new()
+        };
+
+        input.extend(Input {
+            types,
+            skipped,
+            quarantined,
+            findings,
+        });
+    }
+
+    Ok(input)
+}
+
+fn find_issues(type_: &schema::Type) -> impl Iterator<Item = Finding> + '_ {
+    validator::validate(type_).into_iter().map(|issue| Finding {
+        type_name: type_.name.clone(),
+        crate_name: type_.crate_name.clone(),
+        issue,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Document {
+    // Produced by `--format json`.
+    Types {
+        version: u32,
+        types: Vec<schema::Type>,
+    },
+    // Produced by `--format ndjson`.
+    Line {
+        version: u32,
+        #[serde(flatten)]
+        type_: schema::Type,
+    },
+}
+
+/// Reads types exported by `--format json` or `--format ndjson`.
+fn read_json(data: &str) -> eyre::Result<Vec<schema::Type>> {
+    let mut types = Vec::new();
+
+    for document in serde_json::Deserializer::from_str(data).into_iter::<Document>() {
+        let (version, new_types) = match document.wrap_err("invalid JSON")? {
+            Document::Types { version, types } => (version, types),
+            Document::Line { version, type_ } => (version, vec![type_]),
+        };
+
+        if version != schema::VERSION {
+            eyre::bail!(
+                "unsupported schema version {version}, expected {}",
+                schema::VERSION
+            );
+        }
+
+        types.extend(new_types);
     }
 
-    Ok(result)
+    Ok(types)
 }
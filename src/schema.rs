@@ -1,6 +1,11 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+/// The version of the serialized representation.
+///
+/// Increased on every incompatible change of types below.
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Type {
     pub name: String,
     pub size: usize,
@@ -8,42 +13,69 @@ pub struct Type {
     pub kind: TypeKind,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub end_padding: Option<usize>,
+    /// A crate in which the type was laid out, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crate_name: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum TypeKind {
     Struct(StructType),
     Enum(EnumType),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct StructType {
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub items: Vec<FieldOrPadding>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct EnumType {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub discriminant_size: Option<usize>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub variants: Vec<EnumVariant>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct EnumVariant {
     pub name: String,
     pub size: usize,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub items: Vec<FieldOrPadding>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum FieldOrPadding {
     Field(Field),
     Padding(usize),
 }
 
+impl Type {
+    /// Returns all fields, including fields of all variants.
+    pub fn fields(&self) -> impl Iterator<Item = &Field> {
+        let items: Box<dyn Iterator<Item = &FieldOrPadding>> = match &self.kind {
+            TypeKind::Struct(s) => Box::new(s.items.iter()),
+            TypeKind::Enum(e) => Box::new(e.variants.iter().flat_map(|v| v.items.iter())),
+        };
+
+        items.filter_map(|item| match item {
+            FieldOrPadding::Field(field) => Some(field),
+            FieldOrPadding::Padding(_) => None,
+        })
+    }
+}
+
+impl EnumType {
+    /// Returns the first one of the largest variants.
+    pub fn largest_variant(&self) -> Option<&EnumVariant> {
+        self.variants
+            .iter()
+            .reduce(|max, v| if v.size > max.size { v } else { max })
+    }
+}
+
 impl FieldOrPadding {
     pub fn size(&self) -> usize {
         match self {
@@ -53,7 +85,19 @@ impl FieldOrPadding {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+/// Returns the total size of paddings among items, the end padding of a type
+/// isn't included.
+pub fn paddings(items: &[FieldOrPadding]) -> usize {
+    items
+        .iter()
+        .map(|item| match item {
+            FieldOrPadding::Padding(size) => *size,
+            FieldOrPadding::Field(_) => 0,
+        })
+        .sum()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Field {
     pub kind: FieldKind,
     pub name: String,
@@ -66,7 +110,7 @@ pub struct Field {
     pub local_type: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum FieldKind {
     AdtField,
     Upvar,
@@ -5,7 +5,11 @@ use std::{
 
 use regex::Regex;
 
-use crate::{options::Options, schema::*};
+use crate::{
+    graph::{NodeId, TypeGraph},
+    options::Options,
+    schema::*,
+};
 
 /// Filters all types by size, regex filters and wrappers.
 fn filter_types(types: &mut Vec<Type>, options: &Options) {
@@ -14,36 +18,54 @@ fn filter_types(types: &mut Vec<Type>, options: &Options) {
         && options.hide_less.is_none()
         && options.filter.is_empty()
         && options.exclude.is_empty()
+        && options.crates.is_empty()
+        && options.exclude_crate.is_empty()
     {
         return;
     }
 
-    types.retain(|type_| {
-        // Remove by size.
-        if type_.size < options.hide_less.unwrap_or(0) {
-            return false;
-        }
+    types.retain(|type_| is_visible(type_, options));
+}
 
-        // Remove wrappers (`MaybeUninit` etc).
-        if options.remove_wrappers && is_wrapper(type_) {
-            return false;
-        }
+/// Checks if a type passes size, regex filters, crate filters and wrappers.
+pub(crate) fn is_visible(type_: &Type, options: &Options) -> bool {
+    // Remove by size.
+    if type_.size < options.hide_less.unwrap_or(0) {
+        return false;
+    }
+
+    // Remove wrappers (`MaybeUninit` etc).
+    if options.remove_wrappers && is_wrapper(type_) {
+        return false;
+    }
+
+    // Remove by explicit patterns.
+    if options
+        .exclude
+        .iter()
+        .any(|pattern| pattern.is_match(&type_.name))
+    {
+        return false;
+    }
+
+    // Remove by crates, types from unknown crates are removed if any
+    // `--crate` pattern is provided.
+    let crate_name = type_.crate_name.as_deref();
+    let match_crate = |pattern: &Regex| crate_name.is_some_and(|name| pattern.is_match(name));
+
+    if options.exclude_crate.iter().any(match_crate) {
+        return false;
+    }
+
+    if !options.crates.is_empty() && !options.crates.iter().any(match_crate) {
+        return false;
+    }
 
-        // Remove by explicit patterns.
-        if options
-            .exclude
+    options.filter.is_empty()
+        || options
+            .filter
             .iter()
             .any(|pattern| pattern.is_match(&type_.name))
-        {
-            return false;
-        }
-
-        options.filter.is_empty()
-            || options
-                .filter
-                .iter()
-                .any(|pattern| pattern.is_match(&type_.name))
-    });
 }
 
 /// Detects wrappers like `MaybeUninit` and custom ones.
@@ -66,46 +88,40 @@ fn is_wrapper(type_: &Type) -> bool {
 
 /// Retains only types that match patterns and their children using fields'
 /// types when provided. If `use_size` is true, it also uses sizes in fields.
-///
-/// Types must be sorted in descending order by size.
 fn expand(types: &mut Vec<Type>, patterns: &[Regex], use_size: bool) {
+    retain_reachable(types, patterns, use_size, |g, ids| g.descendants(ids));
+}
+
+/// Retains only types that match patterns and types containing them using
+/// fields' types when provided. If `use_size` is true, it also uses sizes in
+/// fields.
+fn expand_parents(types: &mut Vec<Type>, patterns: &[Regex], use_size: bool) {
+    retain_reachable(types, patterns, use_size, |g, ids| g.ancestors(ids));
+}
+
+fn retain_reachable(
+    types: &mut Vec<Type>,
+    patterns: &[Regex],
+    use_size: bool,
+    reachable: impl Fn(&TypeGraph<'_>, Vec<NodeId>) -> HashSet<NodeId>,
+) {
     if patterns.is_empty() {
         return;
     }
 
-    let mut names = HashSet::new();
-    let mut sizes = HashSet::new();
+    let graph = TypeGraph::new(types, use_size);
+    let matched = (0..types.len())
+        .filter(|&id| patterns.iter().any(|p| p.is_match(&types[id].name)))
+        .collect();
 
-    types.retain(|type_| {
-        if !names.contains(&type_.name)
-            && !sizes.contains(&type_.size)
-            && !patterns.iter().any(|p| p.is_match(&type_.name))
-        {
-            return false;
-        }
-
-        let append = |f: &FieldOrPadding| match f {
-            FieldOrPadding::Field(f) => {
-                if let Some(name) = &f.local_type {
-                    names.insert(name.clone());
-                } else if use_size {
-                    sizes.insert(f.size);
-                }
-            }
-            FieldOrPadding::Padding(_) => {}
-        };
-
-        match &type_.kind {
-            TypeKind::Struct(s) => s.items.iter().for_each(append),
-            TypeKind::Enum(e) => e
-                .variants
-                .iter()
-                .flat_map(|variant| variant.items.iter())
-                .for_each(append),
-        }
+    // Edges by name lead to the first type with the name, so retain all
+    // types with the same name.
+    let retained = reachable(&graph, matched)
+        .into_iter()
+        .map(|id| types[id].name.clone())
+        .collect::<HashSet<_>>();
 
-        true
-    });
+    types.retain(|type_| retained.contains(&type_.name));
 }
 
 /// Sorts all variants and merges ones with similar layouts.
@@ -133,6 +149,7 @@ fn sort_and_merge_variants(type_: &mut Type) {
         }
     }
 
+    #[allow(clippy::iter_kv_map)]
     let mut new = variants
         .into_iter()
         .flat_map(|(_, vec)| vec)
@@ -191,16 +208,39 @@ pub fn transform(mut types: Vec<Type>, options: &Options) -> Vec<Type> {
     // Use stable sort to preserve partial ordering.
     // Also sort by name to do proper deduplication.
     types.sort_by(|a, b| (b.size, &b.name).cmp(&(a.size, &a.name)));
-    types.dedup();
 
-    expand(&mut types, &options.expand, options.expand_by_size);
+    // The same type can be laid out in several crates, keep the first one.
+    types.dedup_by(|a, b| {
+        a.name == b.name
+            && a.size == b.size
+            && a.align == b.align
+            && a.kind == b.kind
+            && a.end_padding == b.end_padding
+    });
 
-    if let Some(limit) = options.limit {
+    expand(&mut types, &options.expand, options.expand_by_size);
+    expand_parents(&mut types, &options.parents, options.expand_by_size);
+    // Breakdowns follow only fields' types.
+    expand(&mut types, &options.blame, false);
+
+    // The tree, parents and analysis views limit roots and found types
+    // instead.
+    if let Some(limit) = options.limit.filter(|_| {
+        !options.is_tree_view()
+            && !options.is_parents_view()
+            && !options.is_blame_view()
+            && !options.is_large_variants_view()
+            && !options.is_waste_view()
+            && !options.is_held_locals_view()
+            && !options.is_duplicated_upvars_view()
+            && !options.is_await_stats_view()
+    }) {
         types.truncate(limit);
     }
 
     for type_ in &mut types {
-        if let Some(threshold) = options.hide_less {
+        // Paddings are counted in full, `-h` hides only small types there.
+        if let Some(threshold) = options.hide_less.filter(|_| !options.is_waste_view()) {
             remove_small_fields(type_, threshold);
         }
 
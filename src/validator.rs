@@ -0,0 +1,342 @@
+use std::fmt;
+
+use crate::schema::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The layout is unusual, but can be correct.
+    Warning,
+    /// The layout is definitely inconsistent.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// An inconsistency in a type's layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// The size isn't a multiple of the alignment.
+    UnalignedSize,
+    /// Fields and paddings of a struct don't add up to its size.
+    SizeMismatch { items_size: usize },
+    /// An enum has a discriminant, but no variants.
+    NoVariants,
+    /// A variant (or its fields) is larger than the enum.
+    LargeVariant { variant: String, size: usize },
+    /// A variant doesn't fit into the enum with the discriminant.
+    VariantOverflow { variant: String, size: usize },
+    /// Fields and paddings of a variant (with the discriminant) don't add up
+    /// to its size.
+    VariantSizeMismatch { variant: String, items_size: usize },
+    /// A field's offset is less than the end of the previous item.
+    UnorderedOffset { field: String, offset: usize },
+    /// A field's offset isn't a multiple of its alignment.
+    MisalignedField {
+        field: String,
+        offset: usize,
+        align: usize,
+    },
+}
+
+impl Issue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::UnalignedSize
+            | Self::SizeMismatch { .. }
+            | Self::NoVariants
+            | Self::LargeVariant { .. }
+            | Self::VariantSizeMismatch { .. } => Severity::Error,
+            Self::VariantOverflow { .. }
+            | Self::UnorderedOffset { .. }
+            | Self::MisalignedField { .. } => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnalignedSize => write!(f, "size isn't a multiple of alignment"),
+            Self::SizeMismatch { items_size } => {
+                write!(f, "fields and paddings add up to {items_size} bytes")
+            }
+            Self::NoVariants => write!(f, "no variants"),
+            Self::LargeVariant { variant, size } => {
+                write!(f, "variant {variant} takes {size} bytes")
+            }
+            Self::VariantOverflow { variant, size } => write!(
+                f,
+                "variant {variant} takes {size} bytes with the discriminant"
+            ),
+            Self::VariantSizeMismatch {
+                variant,
+                items_size,
+            } => write!(
+                f,
+                "fields and paddings of variant {variant} add up to {items_size} bytes"
+            ),
+            Self::UnorderedOffset { field, offset } => {
+                write!(
+                    f,
+                    "field {field} at offset {offset} overlaps the previous one"
+                )
+            }
+            Self::MisalignedField {
+                field,
+                offset,
+                align,
+            } => write!(
+                f,
+                "field {field} at offset {offset} isn't aligned to {align}"
+            ),
+        }
+    }
+}
+
+/// An issue found in a specific type.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub type_name: String,
+    pub crate_name: Option<String>,
+    pub issue: Issue,
+}
+
+/// Checks that the layout of a type is consistent.
+///
+/// Must be applied only to the compiler's output, because exported JSON can
+/// contain partial layouts (e.g. after `-h` or `-s`).
+pub fn validate(type_: &Type) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    if !type_.size.is_multiple_of(type_.align) {
+        issues.push(Issue::UnalignedSize);
+    }
+
+    match &type_.kind {
+        TypeKind::Struct(struct_) => {
+            // The compiler prints all paddings of structs.
+            let items_size = items_size(&struct_.items) + type_.end_padding.unwrap_or(0);
+            if items_size != type_.size {
+                issues.push(Issue::SizeMismatch { items_size });
+            }
+
+            validate_offsets(&struct_.items, 0, &mut issues);
+        }
+        TypeKind::Enum(enum_) if is_union(enum_) => {
+            // Fields of a union overlap, so only the largest one must fit.
+            let variant = &enum_.variants[0];
+            let size = variant
+                .items
+                .iter()
+                .map(|i| i.size())
+                .fold(variant.size, usize::max);
+
+            if size > type_.size {
+                issues.push(Issue::LargeVariant {
+                    variant: variant.name.clone(),
+                    size,
+                });
+            }
+        }
+        TypeKind::Enum(enum_) => {
+            let discriminant_size = enum_.discriminant_size.unwrap_or(0);
+
+            if enum_.discriminant_size.is_some() && enum_.variants.is_empty() {
+                issues.push(Issue::NoVariants);
+            }
+
+            // Layouts of coroutine variants include the discriminant and
+            // the prefix of upvars inconsistently, so only bounds work.
+            for variant in &enum_.variants {
+                let items_size = items_size(&variant.items);
+                let size = variant.size.max(items_size);
+
+                if size > type_.size {
+                    issues.push(Issue::LargeVariant {
+                        variant: variant.name.clone(),
+                        size,
+                    });
+                } else if variant.size + discriminant_size > type_.size {
+                    issues.push(Issue::VariantOverflow {
+                        variant: variant.name.clone(),
+                        size: variant.size + discriminant_size,
+                    });
+                }
+
+                if items_size + discriminant_size < variant.size {
+                    issues.push(Issue::VariantSizeMismatch {
+                        variant: variant.name.clone(),
+                        items_size,
+                    });
+                }
+
+                // Fields of variants are placed after the discriminant.
+                validate_offsets(&variant.items, discriminant_size, &mut issues);
+            }
+        }
+    }
+
+    issues
+}
+
+// The compiler prints a union as a single variant without a discriminant,
+// where fields have explicit offsets overlapping previous fields.
+fn is_union(enum_: &EnumType) -> bool {
+    let [variant] = &enum_.variants[..] else {
+        return false;
+    };
+
+    let mut cursor = None;
+    enum_.discriminant_size.is_none()
+        && variant.items.iter().any(|item| {
+            let FieldOrPadding::Field(field) = item else {
+                return false;
+            };
+
+            let offset = field.offset.unwrap_or(cursor.unwrap_or(0));
+            let overlaps = cursor.is_some_and(|cursor| offset < cursor);
+            cursor = Some(offset + field.size);
+            overlaps
+        })
+}
+
+fn items_size(items: &[FieldOrPadding]) -> usize {
+    items.iter().map(|i| i.size()).sum()
+}
+
+fn validate_offsets(items: &[FieldOrPadding], start: usize, issues: &mut Vec<Issue>) {
+    let mut cursor = start;
+
+    for (index, item) in items.iter().enumerate() {
+        let FieldOrPadding::Field(field) = item else {
+            cursor += item.size();
+            continue;
+        };
+
+        let offset = match field.offset {
+            // The first explicit offset can be anywhere, e.g. upvars of
+            // coroutines are placed before the discriminant.
+            Some(offset) if index == 0 => offset,
+            Some(offset) => {
+                if offset < cursor {
+                    issues.push(Issue::UnorderedOffset {
+                        field: field.name.clone(),
+                        offset,
+                    });
+                }
+                offset
+            }
+            None => cursor,
+        };
+
+        if let Some(align) = field.align.filter(|&align| !offset.is_multiple_of(align)) {
+            issues.push(Issue::MisalignedField {
+                field: field.name.clone(),
+                offset,
+                align,
+            });
+        }
+
+        cursor = offset + field.size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn issues(input: &[&str]) -> Vec<Issue> {
+        let types = parser::parse(&input.join("\n")).unwrap();
+        validate(&types[0])
+    }
+
+    #[test]
+    fn test_validate() {
+        let valid = [
+            "type: `A`: 16 bytes, alignment: 8 bytes",
+            "    field `.a`: 4 bytes",
+            "    padding: 4 bytes",
+            "    field `.b`: 8 bytes, alignment: 8 bytes",
+        ];
+        assert_eq!(issues(&valid), []);
+
+        let misaligned = [
+            "type: `A`: 16 bytes, alignment: 8 bytes",
+            "    field `.a`: 4 bytes",
+            "    field `.b`: 8 bytes, alignment: 8 bytes",
+            "    end padding: 4 bytes",
+        ];
+        assert_eq!(
+            issues(&misaligned),
+            [Issue::MisalignedField {
+                field: "b".into(),
+                offset: 4,
+                align: 8
+            }]
+        );
+
+        let unordered = [
+            "type: `A`: 16 bytes, alignment: 8 bytes",
+            "    field `.a`: 8 bytes, offset: 0 bytes, alignment: 8 bytes",
+            "    field `.b`: 8 bytes, offset: 4 bytes",
+        ];
+        assert_eq!(
+            issues(&unordered),
+            [Issue::UnorderedOffset {
+                field: "b".into(),
+                offset: 4
+            }]
+        );
+
+        let overflow = [
+            "type: `E`: 8 bytes, alignment: 4 bytes",
+            "    discriminant: 4 bytes",
+            "    variant `X`: 8 bytes",
+            "        field `.0`: 4 bytes",
+            "        field `.1`: 4 bytes",
+        ];
+        let issues = issues(&overflow);
+        assert_eq!(
+            issues,
+            [Issue::VariantOverflow {
+                variant: "X".into(),
+                size: 12
+            }]
+        );
+        assert_eq!(issues[0].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn test_validate_union() {
+        let union = [
+            "type: `Wide`: 8 bytes, alignment: 4 bytes",
+            "    variant `Wide`: 5 bytes",
+            "        field `.word`: 4 bytes",
+            "        field `.bytes`: 5 bytes, offset: 0 bytes, alignment: 1 bytes",
+            "    end padding: 3 bytes",
+        ];
+        assert_eq!(issues(&union), []);
+
+        let large = [
+            "type: `U`: 8 bytes, alignment: 8 bytes",
+            "    variant `U`: 8 bytes",
+            "        field `.a`: 4 bytes",
+            "        field `.b`: 16 bytes, offset: 0 bytes, alignment: 8 bytes",
+        ];
+        assert_eq!(
+            issues(&large),
+            [Issue::LargeVariant {
+                variant: "U".into(),
+                size: 16
+            }]
+        );
+    }
+}
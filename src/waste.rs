@@ -0,0 +1,115 @@
+use std::cmp::Reverse;
+
+use crate::{options::WasteOrder, schema::*};
+
+/// Paddings of a type.
+#[derive(Debug)]
+pub struct Waste<'a> {
+    pub type_: &'a Type,
+    /// Paddings between fields of a struct or of the largest variant.
+    pub internal: usize,
+    pub end: usize,
+    /// Paddings between fields of every variant with paddings.
+    pub variants: Vec<(&'a EnumVariant, usize)>,
+}
+
+impl Waste<'_> {
+    /// Bytes wasted in every value of the type.
+    pub fn total(&self) -> usize {
+        self.internal + self.end
+    }
+
+    pub fn percent(&self) -> f64 {
+        self.total() as f64 * 100. / self.type_.size.max(1) as f64
+    }
+}
+
+/// Finds types with paddings, ranked by wasted bytes or by their share.
+pub fn find(types: &[Type], order: WasteOrder) -> Vec<Waste<'_>> {
+    let mut found = types
+        .iter()
+        .map(waste)
+        .filter(|w| w.total() > 0)
+        .collect::<Vec<_>>();
+
+    match order {
+        WasteOrder::Bytes => found.sort_by_key(|w| Reverse(w.total())),
+        WasteOrder::Percent => {
+            found.sort_by(|a, b| b.percent().total_cmp(&a.percent()));
+        }
+    }
+
+    found
+}
+
+fn waste(type_: &Type) -> Waste<'_> {
+    let (internal, variants) = match &type_.kind {
+        TypeKind::Struct(s) => (paddings(&s.items), Vec::new()),
+        TypeKind::Enum(e) => {
+            let internal = e.largest_variant().map_or(0, |v| paddings(&v.items));
+
+            let variants = e
+                .variants
+                .iter()
+                .map(|v| (v, paddings(&v.items)))
+                .filter(|(_, padding)| *padding > 0)
+                .collect();
+
+            (internal, variants)
+        }
+    };
+
+    Waste {
+        type_,
+        internal,
+        end: type_.end_padding.unwrap_or(0),
+        variants,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    #[test]
+    fn test_find() {
+        let input = [
+            "type: `A`: 64 bytes, alignment: 8 bytes",
+            "    field `.a`: 8 bytes",
+            "    field `.b`: 1 bytes",
+            "    padding: 7 bytes",
+            "    field `.c`: 40 bytes",
+            "    field `.d`: 1 bytes",
+            "    end padding: 7 bytes",
+            "type: `B`: 16 bytes, alignment: 8 bytes",
+            "    discriminant: 1 bytes",
+            "    variant `X`: 15 bytes",
+            "        padding: 7 bytes",
+            "        field `.0`: 8 bytes, alignment: 8 bytes",
+            "    variant `Y`: 3 bytes",
+            "        padding: 1 bytes",
+            "        field `.0`: 2 bytes, alignment: 2 bytes",
+            "type: `C`: 8 bytes, alignment: 8 bytes",
+            "    field `.0`: 8 bytes",
+        ]
+        .join("\n");
+        let types = parser::parse(&input).unwrap();
+
+        let summary = |found: Vec<Waste<'_>>| {
+            found
+                .iter()
+                .map(|w| (w.type_.name.clone(), w.internal, w.end, w.variants.len()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            summary(find(&types, WasteOrder::Bytes)),
+            [("A".into(), 7, 7, 0), ("B".into(), 7, 0, 2)]
+        );
+        assert_eq!(
+            summary(find(&types, WasteOrder::Percent)),
+            [("B".into(), 7, 0, 2), ("A".into(), 7, 7, 0)]
+        );
+    }
+}
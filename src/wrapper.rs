@@ -0,0 +1,120 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use eyre::WrapErr;
+
+use crate::reader;
+
+/// A directory to collect the compiler's output in the wrapper mode.
+pub const DIR_ENV: &str = "TOP_TYPE_SIZES_DIR";
+
+/// Detects if the binary is invoked as `RUSTC_WRAPPER`.
+///
+/// Cargo runs `$RUSTC_WRAPPER $RUSTC <args>`, so the first argument is rustc.
+/// Returns the collection directory and rustc's command line.
+pub fn detect() -> Option<(PathBuf, Vec<OsString>)> {
+    let dir = env::var_os(DIR_ENV)?;
+    let args = env::args_os().skip(1).collect::<Vec<_>>();
+
+    let rustc = Path::new(args.first()?);
+    if !rustc.file_stem()?.to_str()?.starts_with("rustc") {
+        return None;
+    }
+
+    Some((dir.into(), args))
+}
+
+/// Runs rustc with `-Zprint-type-sizes` and writes its stdout to a per-crate
+/// file in `dir`. Other invocations (e.g. `rustc -vV`) are passed through.
+///
+/// Returns the exit code of rustc.
+pub fn run(dir: &Path, args: &[OsString]) -> eyre::Result<i32> {
+    let (rustc, args) = args.split_first().expect("rustc must be provided");
+    let mut command = Command::new(rustc);
+    command.args(args);
+
+    let Some(file_name) = output_file_name(args) else {
+        let status = command.status().wrap_err("cannot run rustc")?;
+        return Ok(status.code().unwrap_or(1));
+    };
+
+    let output = command
+        .arg("-Zprint-type-sizes")
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .wrap_err("cannot run rustc")?;
+
+    // Attribute types to the crate.
+    let mut content = match (env::var("CARGO_PKG_NAME"), env::var("CARGO_PKG_VERSION")) {
+        (Ok(name), Ok(version)) => reader::crate_header(&name, &version).into_bytes(),
+        _ => Vec::new(),
+    };
+    content.extend(output.stdout);
+
+    fs::create_dir_all(dir).wrap_err_with(|| format!("cannot create {}", dir.display()))?;
+    let path = dir.join(file_name);
+    fs::write(&path, content).wrap_err_with(|| format!("cannot write {}", path.display()))?;
+
+    Ok(output.status.code().unwrap_or(1))
+}
+
+/// Returns `<crate name><extra filename>.txt` for compiling invocations.
+fn output_file_name(args: &[OsString]) -> Option<String> {
+    let args = args.iter().filter_map(|a| a.to_str()).collect::<Vec<_>>();
+
+    // Cargo probes the compiler with `--print` and a fake crate name.
+    if args.iter().any(|a| a.starts_with("--print")) {
+        return None;
+    }
+
+    let crate_name = args
+        .windows(2)
+        .find(|w| w[0] == "--crate-name")
+        .map(|w| w[1])?;
+
+    // Distinguishes crates with the same name, e.g. different versions.
+    let extra_filename = args
+        .iter()
+        .enumerate()
+        .find_map(|(i, a)| match *a {
+            "-C" => args.get(i + 1)?.strip_prefix("extra-filename="),
+            _ => a.strip_prefix("-Cextra-filename="),
+        })
+        .unwrap_or("");
+
+    Some(format!("{crate_name}{extra_filename}.txt"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<OsString> {
+        line.split(' ').map(Into::into).collect()
+    }
+
+    #[test]
+    fn test_output_file_name() {
+        let line = "--crate-name chat --edition=2021 -C extra-filename=-3e8a --out-dir /tmp";
+        assert_eq!(output_file_name(&args(line)).unwrap(), "chat-3e8a.txt");
+
+        let line = "--crate-name chat -Cextra-filename=-3e8a";
+        assert_eq!(output_file_name(&args(line)).unwrap(), "chat-3e8a.txt");
+
+        let line = "--crate-name build_script_build --crate-type bin";
+        assert_eq!(
+            output_file_name(&args(line)).unwrap(),
+            "build_script_build.txt"
+        );
+
+        assert_eq!(output_file_name(&args("-vV")), None);
+        let line = "- --crate-name ___ --print=file-names --crate-type bin --print=cfg";
+        assert_eq!(output_file_name(&args(line)), None);
+    }
+}
@@ -3,9 +3,11 @@ use structopt::StructOpt;
 use top_type_sizes::*;
 
 fn test(content: &'static str) {
-    let refined_content = reader::read(content.as_bytes()).unwrap();
+    let types = read(content, &["--input-format=text"]);
+    for type_ in &types {
+        assert_eq!(validator::validate(type_), [], "{}", type_.name);
+    }
 
-    let types = parser::parse(&refined_content).unwrap();
     insta::with_settings!({ description => "internal representation" }, {
         insta::assert_yaml_snapshot!("types", types);
     });
@@ -16,6 +18,14 @@ fn test(content: &'static str) {
     snap_output(&types, &["-ws", "-h16"]);
 }
 
+fn read(content: &str, cmd: &[&str]) -> Vec<schema::Type> {
+    let options = options::Options::from_iter(std::iter::once(&"top-type-sizes").chain(cmd));
+    let input = reader::read_types(content.as_bytes(), &options).unwrap();
+    assert!(input.skipped.is_empty());
+    assert!(input.quarantined.is_empty());
+    input.types
+}
+
 fn snap_output(types: &[schema::Type], cmd: &[&str]) {
     let full_cmd = std::iter::once(&"top-type-sizes")
         .chain(cmd)
@@ -37,6 +47,43 @@ fn snap_output(types: &[schema::Type], cmd: &[&str]) {
     })
 }
 
+fn snap_diff(old: &str, new: &str, cmd: &[&str]) {
+    let full_cmd = std::iter::once(&"top-type-sizes")
+        .chain(cmd)
+        .copied()
+        .collect::<Vec<_>>();
+
+    let options = options::Options::from_iter(&full_cmd);
+    let snap_name = {
+        let mut parts = cmd.to_vec();
+        parts.insert(0, "diff");
+        parts.join("")
+    };
+
+    let old = parser::parse(&reader::read(old.as_bytes()).unwrap()).unwrap();
+    let new = parser::parse(&reader::read(new.as_bytes()).unwrap()).unwrap();
+    let diffs = differ::diff(&old, &new, &options);
+    let output = formatter::format_diff(diffs, &options);
+
+    insta::with_settings!({ description => full_cmd.join(" ") }, {
+        insta::assert_snapshot!(snap_name, output);
+    })
+}
+
+/// Snapshots output of the chat sample, read the same way as by the binary.
+fn snap_chat(cmd: &[&str]) {
+    insta::with_settings!(
+    {
+        snapshot_path => "snapshots/chat",
+        prepend_module_to_snapshot => false,
+        omit_expression => true,
+    },
+    {
+        let types = read(include_str!("samples/chat.txt"), &[]);
+        snap_output(&types, cmd);
+    })
+}
+
 macro_rules! test_sample {
     ($name:ident) => {
         #[test]
@@ -62,5 +109,400 @@ test_sample!(several_types);
 test_sample!(timex);
 test_sample!(async_fn);
 test_sample!(compiler_messages);
+test_sample!(chat);
+test_sample!(crates);
+test_sample!(union);
+
+#[test]
+fn crate_filters() {
+    insta::with_settings!(
+    {
+        snapshot_path => "snapshots/crates",
+        prepend_module_to_snapshot => false,
+        omit_expression => true,
+    },
+    {
+        let content = include_str!("samples/crates.txt");
+        let types = read(content, &[]);
+
+        snap_output(&types, &["--crate=proj"]);
+        snap_output(&types, &["--exclude-crate=proj"]);
+
+        // Crate headers take precedence over cargo's `Compiling` lines.
+        let content = content
+            .replace("   Compiling itoa", "top-type-sizes: crate itoa")
+            .replace("   Compiling proj", "top-type-sizes: crate proj")
+            .replace("print-type-size type: `Buffer`", "   Compiling other v0.1.0\nprint-type-size type: `Buffer`");
+        let types_by_headers = read(&content, &[]);
+        assert_eq!(types_by_headers, types);
+    })
+}
+
+#[test]
+fn diff() {
+    insta::with_settings!(
+    {
+        snapshot_path => "snapshots/diff",
+        prepend_module_to_snapshot => false,
+        omit_expression => true,
+    },
+    {
+        let old = include_str!("samples/chat.txt");
+        let new = include_str!("samples/chat_new.txt");
+
+        snap_diff(old, new, &[]);
+        snap_diff(old, new, &["-h30"]);
+        snap_diff(old, new, &["-f", "async"]);
+        snap_diff(old, new, &["--format=markdown"]);
+    })
+}
+
+#[test]
+fn json() {
+    snap_chat(&["-ws", "-h8", "-l3", "--format=json"]);
+    snap_chat(&["-ws", "-h8", "-l3", "--format=ndjson"]);
+}
+
+#[test]
+fn tree() {
+    snap_chat(&["-ws", "-h33", "-p", "process", "--tree"]);
+    snap_chat(&["-ws", "-h33", "-l1", "--tree", "--tree-depth=1"]);
+    snap_chat(&[
+        "-ws",
+        "-h16",
+        "-p",
+        "broadcast",
+        "--tree",
+        "--expand-by-size",
+    ]);
+}
+
+#[test]
+fn parents() {
+    snap_chat(&["--parents", "Acquire", "--parents", "process"]);
+    snap_chat(&["--parents", "Message", "--expand-by-size", "-h16"]);
+    snap_chat(&["--who-contains", "Acquire", "--format=ndjson", "-s"]);
+}
+
+#[test]
+fn expand_by_size_many_types() {
+    // Every type contains all others by size.
+    let content = (0..5000)
+        .map(|i| {
+            format!(
+                "print-type-size type: `T{i}`: 8 bytes, alignment: 8 bytes\n\
+                 print-type-size     field `.0`: 8 bytes\n"
+            )
+        })
+        .collect::<String>();
+    let types = read(&content, &[]);
+
+    for option in ["-p", "--parents"] {
+        let cmd = ["top-type-sizes", option, "^T0$", "--expand-by-size"];
+        let options = options::Options::from_iter(cmd);
+        assert_eq!(transformer::transform(types.clone(), &options).len(), 5000);
+    }
+}
+
+#[test]
+fn tree_expand_by_size_many_types() {
+    // Every field contains all `U*` types by size.
+    let content = (0..300)
+        .map(|i| {
+            format!(
+                "print-type-size type: `T{i}`: 24 bytes, alignment: 8 bytes\n\
+                 print-type-size     field `.0`: 8 bytes\n\
+                 print-type-size     field `.1`: 8 bytes\n\
+                 print-type-size     field `.2`: 8 bytes\n\
+                 print-type-size type: `U{i}`: 8 bytes, alignment: 8 bytes\n\
+                 print-type-size     field `.0`: 8 bytes\n"
+            )
+        })
+        .collect::<String>();
+    let types = read(&content, &[]);
+
+    let cmd = ["top-type-sizes", "--tree", "--expand-by-size", "-l1"];
+    let options = options::Options::from_iter(cmd);
+    let output = formatter::format(types, &options);
+
+    let lines = output.lines().collect::<Vec<_>>();
+    // `U*` types are expanded once, in a chain.
+    let cycles = lines.iter().filter(|l| l.ends_with("(cycle)")).count();
+    assert_eq!(cycles, 300 * 299 / 2);
+    assert_eq!(lines.len(), 90904);
+}
+
+#[test]
+fn parents_expand_by_size_many_types() {
+    // Every type contains all others by size, so chains are long and many.
+    let content = (0..2000)
+        .map(|i| {
+            format!(
+                "print-type-size type: `T{i}`: 8 bytes, alignment: 8 bytes\n\
+                 print-type-size     field `.0`: 8 bytes\n"
+            )
+        })
+        .collect::<String>();
+    let types = read(&content, &[]);
+
+    let cmd = ["top-type-sizes", "--parents", "^T0$", "--expand-by-size"];
+    let options = options::Options::from_iter(cmd);
+    let output = formatter::format(types, &options);
+
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1 + 32 + 1);
+    assert!(lines[1..33].iter().all(|l| l.ends_with("→ T0")));
+    assert_eq!(lines[33], "    (more chains omitted)");
+}
+
+#[test]
+fn blame() {
+    snap_chat(&["--blame", "process"]);
+    snap_chat(&["--blame", "Mutex", "-l1", "-h8"]);
+    snap_chat(&["--blame", "broadcast", "--format=ndjson", "-s"]);
+}
+
+#[test]
+fn dot() {
+    snap_chat(&["--format=dot", "-p", "process"]);
+    snap_chat(&[
+        "--format=dot",
+        "-h16",
+        "-p",
+        "broadcast",
+        "--expand-by-size",
+    ]);
+    snap_chat(&["--format=dot", "-l3", "-e", "Acquire"]);
+}
+
+#[test]
+fn folded() {
+    snap_chat(&["--format=folded", "-p", "Peer..new"]);
+    snap_chat(&["--format=folded", "-h16", "-l3"]);
+}
+
+#[test]
+fn html() {
+    snap_chat(&["--format=html", "-p", "Peer..new", "-h8"]);
+}
+
+#[test]
+fn markdown() {
+    snap_chat(&["--format=markdown", "-ws", "-h16", "-l3"]);
+}
+
+#[test]
+fn csv() {
+    snap_chat(&["--format=csv", "-f", "Peer", "-f", "Acquire"]);
+    snap_chat(&["--format=csv", "--granularity=type", "-l5"]);
+    snap_chat(&["--format=tsv", "--granularity=variant", "-h16", "-l3"]);
+}
+
+#[test]
+fn color() {
+    snap_chat(&["--color=always", "-l2"]);
+    snap_chat(&["--color=always", "--blame", "read_username", "-h4"]);
+}
+
+#[test]
+fn large_variants() {
+    snap_chat(&["--large-variants"]);
+    snap_chat(&["--large-variants", "--min-variant-ratio=4", "-l2"]);
+    snap_chat(&["--large-variants", "--min-variant-bytes=1000"]);
+
+    let cmd = ["top-type-sizes", "--min-variant-ratio=4"];
+    assert!(options::Options::from_iter_safe(cmd).is_err());
+}
+
+#[test]
+fn waste() {
+    snap_chat(&["--waste"]);
+    snap_chat(&["--waste", "--waste-by=percent", "-l3"]);
+    snap_chat(&["--waste", "-f", "Peer"]);
+    snap_chat(&["--waste", "-h8"]);
+}
+
+#[test]
+fn held_locals() {
+    snap_chat(&["--held-locals"]);
+    snap_chat(&["--held-locals", "-h64", "-l2"]);
+}
+
+#[test]
+fn duplicated_upvars() {
+    snap_chat(&["--duplicated-upvars"]);
+    snap_chat(&["--duplicated-upvars", "-f", "broadcast"]);
+}
+
+#[test]
+fn await_stats() {
+    snap_chat(&["--await-stats"]);
+    snap_chat(&["--await-stats", "--await-stats-by=share", "-l3"]);
+}
+
+#[test]
+fn json_input() {
+    let content = include_str!("samples/chat.txt");
+    let expected = read(content, &[]);
+    assert_eq!(expected.len(), 14);
+
+    for format in ["json", "ndjson"] {
+        let options = options::Options::from_iter(["top-type-sizes", "--format", format]);
+        let output = formatter::format(expected.clone(), &options);
+
+        let actual = read(&output, &[]);
+        assert_eq!(actual, expected);
+
+        let actual = read(&output, &["--input-format=json"]);
+        assert_eq!(actual, expected);
+    }
+
+    // Exported layouts aren't validated.
+    let options = options::Options::from_iter(["top-type-sizes", "--format=json"]);
+    let output = formatter::format(expected.clone(), &options);
+    let options = options::Options::from_iter(["top-type-sizes", "--validate"]);
+    assert!(reader::read_types(output.as_bytes(), &options).is_err());
+
+    let output = r#"{"version":0,"types":[]}"#;
+    let options = options::Options::default();
+    assert!(reader::read_types(output.as_bytes(), &options).is_err());
+    let types = read(output, &["--input-format=text"]);
+    assert!(types.is_empty());
+}
+
+#[test]
+fn directory_input() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("chat-1.txt"),
+        include_str!("samples/chat.txt"),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("command.txt"),
+        include_str!("samples/command.txt"),
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("ignored.log"), "garbage").unwrap();
+
+    let options = options::Options::default();
+    let types = reader::read_path(dir.path(), &options).unwrap().types;
+    assert_eq!(types.len(), 15);
+    assert_eq!(types[0].name, "{async fn body of process()}");
+    assert_eq!(
+        types[14].name,
+        "std::sys::unix::process::process_common::Command"
+    );
+}
+
+#[test]
+fn check() {
+    let content = include_str!("samples/chat.txt");
+    let types = parser::parse(&reader::read(content.as_bytes()).unwrap()).unwrap();
+    let rules = checker::parse_rules(concat!(
+        "`{async fn body of process()}` <= 512\n",
+        "Peer::new <= 1024\n",
+        "^chat:: <= 128\n",
+        "unknown <= 8\n",
+    ))
+    .unwrap();
+
+    let violations = checker::check(&types, &rules, true);
+    assert_eq!(
+        checker::summary(&violations, &rules),
+        "3 of 4 rules violated"
+    );
+
+    let violations = violations.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        violations,
+        [
+            "{async fn body of process()}: 696 bytes > 512 bytes (line 1: `{async fn body of process()}` <= 512)",
+            "chat::Message: 152 bytes > 128 bytes (line 3: ^chat:: <= 128)",
+            "chat::Peer: 152 bytes > 128 bytes (line 3: ^chat:: <= 128)",
+            "no types match the rule (line 4: unknown <= 8)",
+        ]
+    );
+    assert_eq!(checker::check(&types, &rules[1..2], true).len(), 0);
+
+    // A rule violated by several types is counted once.
+    let violations = checker::check(&types, &rules[2..3], false);
+    assert_eq!(violations.len(), 2);
+    assert_eq!(
+        checker::summary(&violations, &rules[2..3]),
+        "1 of 1 rules violated"
+    );
+}
+
+#[test]
+fn build_args() {
+    let cmd = "top-type-sizes -l3 build -o out.txt --release --example chat -o x";
+    let options = options::Options::from_iter(cmd.split(' '));
+    let Some(options::Command::Build {
+        output, cargo_args, ..
+    }) = options.command
+    else {
+        panic!("not a build: {:?}", options.command);
+    };
+
+    assert_eq!(output.to_str(), Some("out.txt"));
+    assert_eq!(cargo_args, ["--release", "--example", "chat", "-o", "x"]);
+}
+
+#[test]
+fn lenient() {
+    // Simulate output interleaved by a parallel build.
+    let content = include_str!("samples/chat.txt").replacen(
+        "print-type-size     variant `Unresumed`: 80 bytes",
+        "print-type-size     variant `Unresumed`: 80 bytes, alignment: 8 byt",
+        1,
+    );
+
+    let options = options::Options::default();
+    assert!(reader::read_types(content.as_bytes(), &options).is_err());
+
+    let options = options::Options::from_iter(["top-type-sizes", "--lenient"]);
+    let input = reader::read_types(content.as_bytes(), &options).unwrap();
+    assert_eq!(input.types.len(), 13);
+
+    // Only the beginning of the broken type is parsed.
+    assert_eq!(input.quarantined.len(), 1);
+    let quarantined = &input.quarantined[0];
+    assert_eq!(quarantined.type_.name, "{async fn body of process()}");
+    assert_eq!(
+        quarantined.reason,
+        interleaving::Reason::Invalid(validator::Issue::VariantSizeMismatch {
+            variant: "Unresumed".into(),
+            items_size: 0,
+        })
+    );
+
+    assert_eq!(input.skipped.len(), 1);
+    assert_eq!(input.skipped[0].line_no, 3);
+    assert_eq!(input.skipped[0].line_count, 39);
+
+    // Line numbers point into the input, not into the crate's output.
+    let content = format!("warning: unused\ntop-type-sizes: crate chat v0.1.0\n{content}");
+    let input = reader::read_types(content.as_bytes(), &options).unwrap();
+    assert_eq!(input.skipped[0].line_no, 5);
+    let marked = input.skipped[0]
+        .context
+        .lines()
+        .find(|l| l.starts_with(">>>"));
+    assert!(marked.unwrap().contains(" 5 │ "));
+
+    // Inconsistent types of a parsable capture are kept without --lenient.
+    let content = include_str!("samples/chat.txt").replacen(
+        "type: `chat::Peer`: 152 bytes",
+        "type: `chat::Peer`: 144 bytes",
+        1,
+    );
+    let input = reader::read_types(content.as_bytes(), &options::Options::default()).unwrap();
+    assert_eq!(input.types.len(), 14);
+    assert!(input.quarantined.is_empty());
+    let input = reader::read_types(content.as_bytes(), &options).unwrap();
+    assert_eq!(input.quarantined[0].type_.name, "chat::Peer");
+}
 
 // TODO: add samples from rustc tests.